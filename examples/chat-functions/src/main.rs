@@ -1,14 +1,30 @@
 use openai_rust::{
-    types::{ChatCompletionRequestBuilder, FunctionBuilder, MessageRequestBuilder, ParametersBuilder, PropertyBuilder, Role},
-    OpenAIClient,
+    types::{ChatCompletionRequestBuilder, Function, MessageRequestBuilder, Role},
+    FunctionParameters, OpenAIClient,
 };
-use serde_json::{self, Value};
-use std::collections::HashMap;
+use serde::Deserialize;
 use std::error::Error;
 use std::env;
 
-fn get_current_weather(location: String) -> String {
-    format!("The weather in {} is 72 degrees and sunny.", location)
+#[derive(Debug, Deserialize, FunctionParameters)]
+#[serde(rename_all = "lowercase")]
+enum Unit {
+    Celsius,
+    Fahrenheit,
+}
+
+#[derive(Debug, Deserialize, FunctionParameters)]
+struct WeatherArguments {
+    /// The city and state, e.g. San Francisco, CA
+    location: String,
+    unit: Option<Unit>,
+}
+
+fn get_current_weather(arguments: WeatherArguments) -> String {
+    match arguments.unit {
+        Some(Unit::Celsius) => format!("The weather in {} is 22 degrees and sunny.", arguments.location),
+        _ => format!("The weather in {} is 72 degrees and sunny.", arguments.location),
+    }
 }
 
 #[tokio::main]
@@ -23,27 +39,9 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
             .build()?
     ];
     
-    let mut properties = HashMap::default();
-    properties.insert(
-        "location".to_string(),
-        PropertyBuilder::default()
-            .param_type("string")
-            .description("The city and state, e.g. San Francisco, CA")
-            .build()?
-    );
-
-    let parameters = ParametersBuilder::default()
-        .param_type("object")
-        .properties(properties)
-        .required(vec!["location".to_string()])
-        .build()?;
-
     let functions = vec![
-        FunctionBuilder::default()
-            .name("get_current_weather")
-            .description("Get the current weather in a given location")
-            .parameters(parameters)
-            .build()?];
+        Function::from_parameters::<WeatherArguments>("get_current_weather", "Get the current weather in a given location")
+    ];
 
     let request = ChatCompletionRequestBuilder::default()
                     .model("gpt-4")
@@ -53,16 +51,10 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
 
     let response = client.chat(request).await?;
 
-    let mut available_functions: HashMap<String, fn(String) -> String> = HashMap::new();
-    available_functions.insert("get_current_weather".to_string(), get_current_weather);
-
     for choice in &response.choices {
         if let Some(function_call) = &choice.message.function_call {
-            if let Some(function_to_call) = available_functions.get(&function_call.name) {
-                let arguments: Value = serde_json::from_str(&function_call.arguments)?;
-
-                let location = arguments["location"].as_str().ok_or("location not found")?.to_string();
-                let function_response = function_to_call(location);
+            if function_call.name == "get_current_weather" {
+                let function_response = get_current_weather(function_call.parse_arguments()?);
 
                 let mut new_messages = messages.clone();
                 new_messages.push(MessageRequestBuilder::default()
@@ -2,6 +2,7 @@ use openai_rust::{
     types::{Role, ChatCompletionRequestBuilder, MessageRequestBuilder},
     OpenAIClient
 };
+use futures::StreamExt;
 use std::error::Error;
 use std::env;
 use std::io::{stdout, Write};
@@ -39,10 +40,11 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
         .model("gpt-4")
         .build()?;
 
-    let mut rx = client.chat_stream(request).await?;
+    let mut stream = client.chat_stream(request).await?;
     
     let mut lock = stdout().lock();
-    while let Some(response) = rx.recv().await {
+    while let Some(response) = stream.next().await {
+        let response = response?;
         for choice in response.choices {
             if let Some(delta) = &choice.delta {
                 if let Some(content) = &delta.content {
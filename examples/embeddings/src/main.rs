@@ -0,0 +1,36 @@
+use openai_rust::{
+    types::{EmbeddingRequestBuilder, EncodingFormat},
+    OpenAIClient
+};
+use std::error::Error;
+use std::env;
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    dot / (norm_a * norm_b)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let api_key = env::var("OPENAI_API_KEY")?;
+    let client = OpenAIClient::new(api_key);
+
+    let request = EmbeddingRequestBuilder::default()
+        .model("text-embedding-3-small")
+        .input(vec!["The cat sits on the mat.", "A kitten rests on a rug.", "Rust is a systems programming language."])
+        .encoding_format(EncodingFormat::Base64)
+        .dimensions(256u32)
+        .build()?;
+
+    let response = client.embeddings(request).await?;
+
+    let first = &response.data[0].embedding;
+    for embedding in &response.data[1..] {
+        println!("Similarity to #{}: {:.3}", embedding.index, cosine_similarity(first, &embedding.embedding));
+    }
+    println!("Tokens used: {}", response.usage.total_tokens);
+
+    Ok(())
+}
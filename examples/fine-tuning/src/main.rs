@@ -0,0 +1,81 @@
+use futures::StreamExt;
+use openai_rust::{
+    types::{FilePurpose, FineTuningJobRequestBuilder},
+    validate_dataset_file, FileUpload, OpenAIClient,
+};
+use std::error::Error;
+use std::env;
+use std::process;
+use std::time::Duration;
+
+const USAGE: &str = "Usage: fine-tuning <validate|create> <training.jsonl> [model]";
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (Some(command), Some(file)) = (args.first(), args.get(1)) else {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    };
+    let model = args.get(2).map(String::as_str).unwrap_or("gpt-4o-mini-2024-07-18");
+
+    match command.as_str() {
+        "validate" => {
+            if !validate(file, model).await? {
+                process::exit(1);
+            }
+        }
+        "create" => {
+            if !validate(file, model).await? {
+                process::exit(1);
+            }
+            create(file, model).await?;
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+
+    Ok(())
+}
+
+async fn validate(file: &str, model: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let report = validate_dataset_file(file, model).await?;
+
+    for issue in &report.issues {
+        println!("{}", issue);
+    }
+    println!("Examples: {}", report.examples);
+    println!("Estimated tokens per epoch: {}", report.total_tokens);
+    println!("Largest example: {} tokens", report.max_example_tokens);
+    match report.estimated_cost_per_epoch {
+        Some(cost) => println!("Estimated cost per epoch: ${:.2}", cost),
+        None => println!("Estimated cost per epoch: unknown for {}", model),
+    }
+
+    Ok(report.is_valid())
+}
+
+async fn create(file: &str, model: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let api_key = env::var("OPENAI_API_KEY")?;
+    let client = OpenAIClient::new(api_key);
+
+    let training_file = client.upload_file(FileUpload::from_path(file)?, FilePurpose::FineTune).await?;
+    let request = FineTuningJobRequestBuilder::default()
+        .model(model)
+        .training_file(training_file.id)
+        .build()?;
+    let job = client.create_fine_tuning_job(request).await?;
+
+    let mut updates = client.wait_for_fine_tune(&job.id, Duration::from_secs(30));
+    while let Some(job) = updates.next().await {
+        let job = job?;
+        println!("{}: {:?}", job.id, job.status);
+        if let Some(fine_tuned_model) = job.fine_tuned_model {
+            println!("Fine-tuned model: {}", fine_tuned_model);
+        }
+    }
+
+    Ok(())
+}
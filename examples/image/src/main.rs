@@ -17,7 +17,9 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     let response = client.image(request).await?;
     
     for image in response.data {
-        println!("Response: {}", image.url);
+        if let Some(url) = image.url() {
+            println!("Response: {}", url);
+        }
     }
 
     Ok(())
@@ -0,0 +1,245 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, meta::ParseNestedMeta, Attribute, Data, DeriveInput, Expr, Fields, LitStr, Token};
+
+#[proc_macro_derive(FunctionParameters, attributes(serde))]
+pub fn derive_function_parameters(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let result = match &input.data {
+        Data::Struct(data) => expand_struct(&input, &data.fields),
+        Data::Enum(data) => expand_enum(&input, data.variants.iter()),
+        Data::Union(_) => Err(syn::Error::new_spanned(&input.ident, "FunctionParameters cannot be derived for unions")),
+    };
+    result.unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+#[derive(Default)]
+struct SerdeAttrs {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    default: bool,
+    skip: bool,
+    flatten: bool,
+}
+
+fn serde_attrs(attrs: &[Attribute]) -> syn::Result<SerdeAttrs> {
+    let mut result = SerdeAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") && meta.input.peek(Token![=]) {
+                result.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("rename_all") && meta.input.peek(Token![=]) {
+                result.rename_all = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("default") {
+                result.default = true;
+                skip_meta(&meta)?;
+            } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                result.skip = true;
+            } else if meta.path.is_ident("flatten") {
+                result.flatten = true;
+            } else {
+                skip_meta(&meta)?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(result)
+}
+
+fn skip_meta(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|nested| skip_meta(&nested))?;
+    }
+    Ok(())
+}
+
+fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs.iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue { value: Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(doc), .. }), .. }) => Some(doc.value().trim().to_string()),
+            _ => None,
+        })
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    if lines.is_empty() { None } else { Some(lines.join(" ")) }
+}
+
+fn rename(name: &str, rule: Option<&str>) -> String {
+    let words: Vec<String> = split_words(name);
+    match rule {
+        Some("lowercase") => name.to_lowercase(),
+        Some("UPPERCASE") => name.to_uppercase(),
+        Some("snake_case") => words.join("_"),
+        Some("SCREAMING_SNAKE_CASE") => words.join("_").to_uppercase(),
+        Some("kebab-case") => words.join("-"),
+        Some("SCREAMING-KEBAB-CASE") => words.join("-").to_uppercase(),
+        Some("camelCase") | Some("PascalCase") => {
+            let pascal: String = words.iter().map(|word| {
+                let mut chars = word.chars();
+                chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+            }).collect();
+            if rule == Some("camelCase") {
+                let mut chars = pascal.chars();
+                chars.next().map(|first| first.to_lowercase().chain(chars).collect::<String>()).unwrap_or_default()
+            } else {
+                pascal
+            }
+        }
+        _ => name.to_string(),
+    }
+}
+
+fn split_words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for c in name.chars() {
+        if c == '_' {
+            if !current.is_empty() { words.push(std::mem::take(&mut current)); }
+        } else if c.is_uppercase() && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+            current.extend(c.to_lowercase());
+        } else {
+            current.extend(c.to_lowercase());
+        }
+    }
+    if !current.is_empty() { words.push(current); }
+    words
+}
+
+fn description_tokens(description: Option<String>) -> TokenStream2 {
+    match description {
+        Some(description) => quote! { property.description = ::std::option::Option::Some(::std::string::String::from(#description)); },
+        None => quote! {},
+    }
+}
+
+fn expand_struct(input: &DeriveInput, fields: &Fields) -> syn::Result<TokenStream2> {
+    let Fields::Named(fields) = fields else {
+        return Err(syn::Error::new_spanned(&input.ident, "FunctionParameters can only be derived for structs with named fields"));
+    };
+
+    let container = serde_attrs(&input.attrs)?;
+    let mut inserts = Vec::new();
+    for field in &fields.named {
+        let attrs = serde_attrs(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+
+        let ty = &field.ty;
+        let defaulted = container.default || attrs.default;
+
+        // The properties of a flattened struct appear directly in the parent object.
+        if attrs.flatten {
+            let required = if defaulted { quote! {} } else { quote! { required.extend(parameters.required); } };
+            inserts.push(quote! {
+                {
+                    let parameters = <#ty as ::openai_rust::FunctionParameters>::parameters();
+                    #required
+                    properties.extend(parameters.properties);
+                }
+            });
+            continue;
+        }
+
+        let ident = field.ident.as_ref().expect("named field");
+        let name = attrs.rename.unwrap_or_else(|| rename(&ident.to_string(), container.rename_all.as_deref()));
+        let description = description_tokens(doc_comment(&field.attrs));
+        let required = if defaulted {
+            quote! {}
+        } else {
+            quote! {
+                if !<#ty as ::openai_rust::SchemaProperty>::is_optional() {
+                    required.push(::std::string::String::from(#name));
+                }
+            }
+        };
+
+        inserts.push(quote! {
+            {
+                let mut property = <#ty as ::openai_rust::SchemaProperty>::property();
+                #description
+                #required
+                properties.insert(::std::string::String::from(#name), property);
+            }
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let description = description_tokens(doc_comment(&input.attrs));
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::openai_rust::FunctionParameters for #ident #ty_generics #where_clause {
+            #[allow(unused_mut)]
+            fn parameters() -> ::openai_rust::types::Parameters {
+                let mut properties = ::std::collections::HashMap::new();
+                let mut required = ::std::vec::Vec::new();
+                #(#inserts)*
+                ::openai_rust::types::Parameters {
+                    param_type: ::std::string::String::from("object"),
+                    properties,
+                    required,
+                    additional_properties: ::std::option::Option::None,
+                }
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_generics ::openai_rust::SchemaProperty for #ident #ty_generics #where_clause {
+            #[allow(unused_mut)]
+            fn property() -> ::openai_rust::types::Property {
+                let parameters = <Self as ::openai_rust::FunctionParameters>::parameters();
+                let mut property = ::openai_rust::types::Property {
+                    param_type: ::std::option::Option::Some(parameters.param_type),
+                    properties: ::std::option::Option::Some(parameters.properties),
+                    required: ::std::option::Option::Some(parameters.required),
+                    ..::std::default::Default::default()
+                };
+                #description
+                property
+            }
+        }
+    })
+}
+
+fn expand_enum<'a>(input: &DeriveInput, variants: impl Iterator<Item = &'a syn::Variant>) -> syn::Result<TokenStream2> {
+    let container = serde_attrs(&input.attrs)?;
+    let mut names = Vec::new();
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(variant, "FunctionParameters can only be derived for enums with unit variants"));
+        }
+        let attrs = serde_attrs(&variant.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        names.push(attrs.rename.unwrap_or_else(|| rename(&variant.ident.to_string(), container.rename_all.as_deref())));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let description = description_tokens(doc_comment(&input.attrs));
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::openai_rust::SchemaProperty for #ident #ty_generics #where_clause {
+            #[allow(unused_mut)]
+            fn property() -> ::openai_rust::types::Property {
+                let mut property = ::openai_rust::types::Property {
+                    param_type: ::std::option::Option::Some(::std::string::String::from("string")),
+                    enum_values: ::std::option::Option::Some(::std::vec![#(::openai_rust::serde_json::Value::from(#names)),*]),
+                    ..::std::default::Default::default()
+                };
+                #description
+                property
+            }
+        }
+    })
+}
@@ -0,0 +1,232 @@
+use crate::error::OpenAIError;
+use crate::types::{ChatCompletionResponse, ChoiceWrapper, Delta, FunctionCall, FunctionCallDelta, MessageResponse, Role, StreamResponse, ToolCall};
+use futures::stream::{Stream, StreamExt};
+use std::collections::BTreeMap;
+
+/// Folds streamed chunks into the response shape returned by `OpenAIClient::chat`.
+#[derive(Debug, Clone, Default)]
+pub struct ChatStreamAccumulator {
+    response: ChatCompletionResponse,
+    choices: BTreeMap<u32, ChoiceWrapper>,
+    tool_calls: BTreeMap<u32, BTreeMap<u32, ToolCall>>,
+}
+
+impl ChatStreamAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the function calls completed by this chunk, i.e. those of every choice that
+    /// finished with `function_call` or `tool_calls`.
+    pub fn push(&mut self, chunk: &StreamResponse) -> Vec<FunctionCall> {
+        let mut completed = Vec::new();
+
+        if self.response.id.is_empty() {
+            self.response.id = chunk.id.clone();
+            self.response.object = "chat.completion".to_string();
+            self.response.created = chunk.created;
+            self.response.model = chunk.model.clone();
+        }
+
+        if let Some(usage) = &chunk.usage {
+            self.response.usage = usage.clone();
+        }
+
+        for choice in &chunk.choices {
+            let entry = self.choices.entry(choice.index).or_insert_with(|| ChoiceWrapper {
+                index: choice.index as i32,
+                message: MessageResponse { role: Role::Assistant, ..Default::default() },
+                finish_reason: String::new(),
+            });
+
+            if let Some(delta) = &choice.delta {
+                let tool_calls = self.tool_calls.entry(choice.index).or_default();
+                Self::apply_delta(&mut entry.message, tool_calls, delta);
+            }
+
+            if let Some(finish_reason) = &choice.finish_reason {
+                entry.finish_reason = finish_reason.clone();
+                match finish_reason.as_str() {
+                    "function_call" => completed.extend(entry.message.function_call.clone()),
+                    "tool_calls" => completed.extend(self.tool_calls(choice.index).into_iter().map(|call| call.function)),
+                    _ => {}
+                }
+            }
+        }
+
+        completed
+    }
+
+    pub fn function_calls(&self, index: u32) -> Vec<FunctionCall> {
+        let message = self.choices.get(&index).map(|choice| &choice.message);
+        message.and_then(|message| message.function_call.clone())
+            .into_iter()
+            .chain(self.tool_calls(index).into_iter().map(|call| call.function))
+            .collect()
+    }
+
+    pub fn tool_calls(&self, index: u32) -> Vec<ToolCall> {
+        self.tool_calls.get(&index).map(|calls| calls.values().cloned().collect()).unwrap_or_default()
+    }
+
+    fn apply_delta(message: &mut MessageResponse, tool_calls: &mut BTreeMap<u32, ToolCall>, delta: &Delta) {
+        if let Some(role) = &delta.role {
+            if let Ok(role) = serde_json::from_value(serde_json::Value::String(role.clone())) {
+                message.role = role;
+            }
+        }
+
+        if let Some(content) = &delta.content {
+            message.content.get_or_insert_with(String::new).push_str(content);
+        }
+
+        if let Some(function_call) = &delta.function_call {
+            Self::append_function_call(message.function_call.get_or_insert_with(FunctionCall::default), function_call);
+        }
+
+        for tool_call in delta.tool_calls.iter().flatten() {
+            let call = tool_calls.entry(tool_call.index).or_default();
+            if let Some(id) = &tool_call.id { call.id = id.clone(); }
+            if let Some(tool_type) = &tool_call.tool_type { call.tool_type = tool_type.clone(); }
+            if let Some(function) = &tool_call.function {
+                Self::append_function_call(&mut call.function, function);
+            }
+        }
+    }
+
+    fn append_function_call(call: &mut FunctionCall, delta: &FunctionCallDelta) {
+        if let Some(name) = &delta.name { call.name.push_str(name); }
+        if let Some(arguments) = &delta.arguments { call.arguments.push_str(arguments); }
+    }
+
+    pub fn response(&self) -> ChatCompletionResponse {
+        self.clone().finish()
+    }
+
+    pub fn finish(mut self) -> ChatCompletionResponse {
+        for (index, choice) in self.choices.iter_mut() {
+            if let Some(calls) = self.tool_calls.remove(index).filter(|calls| !calls.is_empty()) {
+                choice.message.tool_calls = Some(calls.into_values().collect());
+            }
+        }
+
+        ChatCompletionResponse {
+            choices: self.choices.into_values().collect(),
+            ..self.response
+        }
+    }
+
+    /// Drains a `chat_stream`, invoking `on_chunk` for every chunk before it is folded in.
+    pub async fn collect<S, F>(mut stream: S, mut on_chunk: F) -> Result<ChatCompletionResponse, OpenAIError>
+    where
+        S: Stream<Item = Result<StreamResponse, OpenAIError>> + Unpin,
+        F: FnMut(&StreamResponse),
+    {
+        let mut accumulator = Self::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            on_chunk(&chunk);
+            accumulator.push(&chunk);
+        }
+        Ok(accumulator.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn chunk(choices: Value) -> StreamResponse {
+        serde_json::from_value(json!({ "id": "chatcmpl-1", "object": "chat.completion.chunk", "created": 1700000000, "model": "gpt-4o", "choices": choices })).unwrap()
+    }
+
+    fn tool_call_delta(index: u32, id: Option<&str>, name: Option<&str>, arguments: &str) -> Value {
+        json!({ "index": index, "id": id, "type": id.map(|_| "function"), "function": { "name": name, "arguments": arguments } })
+    }
+
+    #[test]
+    fn demultiplexes_interleaved_choices() {
+        let mut accumulator = ChatStreamAccumulator::new();
+        let chunks = [
+            json!([{ "index": 0, "delta": { "role": "assistant", "content": "" } }, { "index": 1, "delta": { "role": "assistant", "content": "" } }]),
+            json!([{ "index": 1, "delta": { "content": "Bon" } }]),
+            json!([{ "index": 0, "delta": { "content": "Hel" } }]),
+            json!([{ "index": 0, "delta": { "content": "lo" } }, { "index": 1, "delta": { "content": "jour" } }]),
+            json!([{ "index": 1, "delta": {}, "finish_reason": "stop" }]),
+            json!([{ "index": 0, "delta": {}, "finish_reason": "length" }]),
+        ];
+        for choices in chunks {
+            assert!(accumulator.push(&chunk(choices)).is_empty());
+        }
+
+        let response = accumulator.finish();
+        assert_eq!(response.id, "chatcmpl-1");
+        assert_eq!(response.object, "chat.completion");
+        assert_eq!(response.model, "gpt-4o");
+        assert_eq!(response.choices.len(), 2);
+        assert_eq!(response.choices[0].index, 0);
+        assert_eq!(response.choices[0].message.content.as_deref(), Some("Hello"));
+        assert_eq!(response.choices[0].finish_reason, "length");
+        assert_eq!(response.choices[1].message.content.as_deref(), Some("Bonjour"));
+        assert_eq!(response.choices[1].finish_reason, "stop");
+        assert!(matches!(response.choices[1].message.role, Role::Assistant));
+        assert!(response.choices[1].message.tool_calls.is_none());
+    }
+
+    #[test]
+    fn joins_tool_call_fragments_by_index() {
+        let mut accumulator = ChatStreamAccumulator::new();
+        let chunks = [
+            json!([{ "index": 0, "delta": { "role": "assistant", "tool_calls": [tool_call_delta(0, Some("call_a"), Some("get_weather"), "")] } }]),
+            json!([{ "index": 0, "delta": { "tool_calls": [tool_call_delta(0, None, None, "{\"city\":")] } }]),
+            json!([{ "index": 0, "delta": { "tool_calls": [tool_call_delta(1, Some("call_b"), Some("get_time"), "{\"zone\"")] } }]),
+            json!([{ "index": 0, "delta": { "tool_calls": [tool_call_delta(0, None, None, "\"Paris\"}"), tool_call_delta(1, None, None, ":\"CET\"}")] } }]),
+        ];
+        for choices in chunks {
+            assert!(accumulator.push(&chunk(choices)).is_empty());
+        }
+
+        let partial = accumulator.response();
+        assert_eq!(partial.choices[0].message.tool_calls.as_ref().map(Vec::len), Some(2));
+        assert_eq!(partial.choices[0].finish_reason, "");
+
+        let completed = accumulator.push(&chunk(json!([{ "index": 0, "delta": {}, "finish_reason": "tool_calls" }])));
+        let completed: Vec<(&str, &str)> = completed.iter().map(|call| (call.name.as_str(), call.arguments.as_str())).collect();
+        assert_eq!(completed, vec![("get_weather", "{\"city\":\"Paris\"}"), ("get_time", "{\"zone\":\"CET\"}")]);
+
+        let response = accumulator.finish();
+        let tool_calls = response.choices[0].message.tool_calls.clone().unwrap();
+        assert_eq!(tool_calls.iter().map(|call| call.id.as_str()).collect::<Vec<_>>(), vec!["call_a", "call_b"]);
+        assert!(tool_calls.iter().all(|call| call.tool_type == "function"));
+        assert_eq!(response.choices[0].message.content, None);
+        assert_eq!(response.choices[0].finish_reason, "tool_calls");
+    }
+
+    #[test]
+    fn completes_legacy_function_calls() {
+        let mut accumulator = ChatStreamAccumulator::new();
+        accumulator.push(&chunk(json!([{ "index": 0, "delta": { "role": "assistant", "function_call": { "name": "get_weather", "arguments": "" } } }])));
+        accumulator.push(&chunk(json!([{ "index": 0, "delta": { "function_call": { "arguments": "{\"city\":\"Rome\"}" } } }])));
+
+        let completed = accumulator.push(&chunk(json!([{ "index": 0, "delta": {}, "finish_reason": "function_call" }])));
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].name, "get_weather");
+        assert_eq!(completed[0].arguments, "{\"city\":\"Rome\"}");
+        assert_eq!(accumulator.function_calls(0).len(), 1);
+    }
+
+    #[test]
+    fn keeps_usage_from_the_final_chunk() {
+        let mut accumulator = ChatStreamAccumulator::new();
+        accumulator.push(&chunk(json!([{ "index": 0, "delta": { "content": "Hi" }, "finish_reason": "stop" }])));
+
+        let mut last = chunk(json!([]));
+        last.usage = serde_json::from_value(json!({ "prompt_tokens": 5, "completion_tokens": 1, "total_tokens": 6 })).unwrap();
+        accumulator.push(&last);
+
+        let response = accumulator.finish();
+        assert_eq!(response.usage.total_tokens, 6);
+        assert_eq!(response.choices[0].message.content.as_deref(), Some("Hi"));
+    }
+}
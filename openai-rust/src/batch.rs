@@ -0,0 +1,176 @@
+use crate::error::{ApiError, OpenAIError};
+use crate::types::{ChatCompletionRequest, ChatCompletionResponse};
+use crate::upload::FileUpload;
+use bytes::Bytes;
+use futures::stream::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+const MAX_BATCH_REQUESTS: usize = 50_000;
+const MAX_BATCH_BYTES: usize = 200 * 1024 * 1024;
+pub(crate) const BATCH_CHAT_ENDPOINT: &str = "/v1/chat/completions";
+
+#[derive(Serialize)]
+struct BatchInputLine<'a> {
+    custom_id: &'a str,
+    method: &'static str,
+    url: &'static str,
+    body: &'a ChatCompletionRequest,
+}
+
+#[derive(Deserialize)]
+struct BatchOutputLine {
+    custom_id: String,
+    response: Option<BatchOutputResponse>,
+    error: Option<ApiError>,
+}
+
+#[derive(Deserialize)]
+struct BatchOutputResponse {
+    status_code: u16,
+    body: Value,
+}
+
+/// Chat completion requests serialized into the JSONL input file of a batch.
+#[derive(Debug, Clone, Default)]
+pub struct BatchInput {
+    jsonl: String,
+    custom_ids: HashSet<String>,
+}
+
+impl BatchInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, custom_id: impl Into<String>, request: &ChatCompletionRequest) -> Result<(), OpenAIError> {
+        let custom_id = custom_id.into();
+        if custom_id.is_empty() {
+            return Err(OpenAIError::Validation("Invalid custom_id: it should not be empty.".to_string()));
+        }
+        if self.custom_ids.contains(&custom_id) {
+            return Err(OpenAIError::Validation(format!("Invalid custom_id: {}. It should be unique within the batch.", custom_id)));
+        }
+        if self.custom_ids.len() >= MAX_BATCH_REQUESTS {
+            return Err(OpenAIError::Validation(format!("Invalid batch: it should contain at most {} requests.", MAX_BATCH_REQUESTS)));
+        }
+        if request.stream == Some(true) {
+            return Err(OpenAIError::Validation(format!("Invalid request {}: batches do not support streaming.", custom_id)));
+        }
+
+        let line = serde_json::to_string(&BatchInputLine { custom_id: &custom_id, method: "POST", url: BATCH_CHAT_ENDPOINT, body: request })
+            .map_err(|e| OpenAIError::Validation(format!("Invalid request {}: {}", custom_id, e)))?;
+        if self.jsonl.len() + line.len() + 1 > MAX_BATCH_BYTES {
+            return Err(OpenAIError::Validation(format!("Invalid batch: the input file should be at most {} bytes.", MAX_BATCH_BYTES)));
+        }
+
+        self.jsonl.push_str(&line);
+        self.jsonl.push('\n');
+        self.custom_ids.insert(custom_id);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.custom_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.custom_ids.is_empty()
+    }
+
+    pub fn to_jsonl(&self) -> &str {
+        &self.jsonl
+    }
+
+    pub fn into_upload(self, file_name: impl Into<String>) -> FileUpload {
+        FileUpload::from_bytes(self.jsonl, file_name, "application/jsonl")
+    }
+}
+
+/// Responses and errors of a finished batch keyed by the custom_id of each request.
+#[derive(Debug, Clone, Default)]
+pub struct BatchResults {
+    pub responses: HashMap<String, ChatCompletionResponse>,
+    pub errors: HashMap<String, ApiError>,
+}
+
+impl BatchResults {
+    /// Parses the lines of an output or error file. Failed requests appear in either file, with
+    /// a non-200 response or an `error` object.
+    pub fn extend_from_jsonl(&mut self, jsonl: &str) -> Result<(), OpenAIError> {
+        jsonl.lines().try_for_each(|line| self.extend_from_line(line))
+    }
+
+    // Parses a file as it is downloaded, so that only the current line is buffered.
+    pub(crate) async fn extend_from_stream<S>(&mut self, mut content: S) -> Result<(), OpenAIError>
+    where
+        S: Stream<Item = Result<Bytes, OpenAIError>> + Unpin,
+    {
+        let mut buffer = Vec::new();
+        while let Some(chunk) = content.next().await {
+            let start = buffer.len();
+            buffer.extend_from_slice(&chunk?);
+            let Some(end) = buffer[start..].iter().rposition(|&b| b == b'\n') else { continue };
+            let rest = buffer.split_off(start + end + 1);
+            self.extend_from_jsonl(&String::from_utf8_lossy(&buffer))?;
+            buffer = rest;
+        }
+        self.extend_from_jsonl(&String::from_utf8_lossy(&buffer))
+    }
+
+    fn extend_from_line(&mut self, line: &str) -> Result<(), OpenAIError> {
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        let output: BatchOutputLine = serde_json::from_str(line)
+            .map_err(|source| OpenAIError::Deserialize { source, body: line.to_string() })?;
+
+        match (output.response, output.error) {
+            (_, Some(error)) => {
+                self.errors.insert(output.custom_id, error);
+            }
+            (Some(response), None) if response.status_code == 200 => {
+                let body = response.body.to_string();
+                let response = serde_json::from_value(response.body)
+                    .map_err(|source| OpenAIError::Deserialize { source, body })?;
+                self.responses.insert(output.custom_id, response);
+            }
+            (Some(response), None) => {
+                let error = response.body.get("error").cloned()
+                    .and_then(|error| serde_json::from_value(error).ok())
+                    .unwrap_or_else(|| ApiError { message: format!("Request failed with status {}: {}", response.status_code, response.body), ..Default::default() });
+                self.errors.insert(output.custom_id, error);
+            }
+            (None, None) => {
+                self.errors.insert(output.custom_id, ApiError { message: "Request has no response".to_string(), ..Default::default() });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    #[tokio::test]
+    async fn parses_lines_split_across_chunks() {
+        let jsonl = concat!(
+            r#"{"custom_id":"a","response":{"status_code":200,"body":{"id":"chatcmpl-1","object":"chat.completion","created":0,"model":"gpt-4o","choices":[],"usage":{"prompt_tokens":1,"completion_tokens":1,"total_tokens":2}}},"error":null}"#, "\n",
+            r#"{"custom_id":"b","response":{"status_code":400,"body":{"error":{"message":"Invalid model","type":"invalid_request_error","param":null,"code":null}}},"error":null}"#, "\n",
+            r#"{"custom_id":"c","response":null,"error":{"message":"Expired","type":null,"param":null,"code":"batch_expired"}}"#,
+        );
+
+        for chunk_size in [1, 7, 64, jsonl.len()] {
+            let chunks: Vec<Result<Bytes, OpenAIError>> = jsonl.as_bytes().chunks(chunk_size).map(|chunk| Ok(Bytes::copy_from_slice(chunk))).collect();
+            let mut results = BatchResults::default();
+            results.extend_from_stream(stream::iter(chunks)).await.unwrap();
+
+            assert_eq!(results.responses["a"].id, "chatcmpl-1", "chunk size {}", chunk_size);
+            assert_eq!(results.errors["b"].message, "Invalid model");
+            assert_eq!(results.errors["c"].code.as_deref(), Some("batch_expired"));
+        }
+    }
+}
@@ -1,142 +1,601 @@
-use crate::types::{ChatCompletionRequest, ChatCompletionResponse, StreamResponse, TranscriptionRequest, TranscriptionResponse, TranslationRequest, TranslationResponse, ImageRequest, ImageResponse};
-use reqwest::{Client, RequestBuilder, Body, multipart::{Form, Part}};
-use std::error::Error;
-use tokio::sync::mpsc::{self, UnboundedSender, UnboundedReceiver};
-use tokio_util::codec::{BytesCodec, FramedRead};
-use futures::stream::StreamExt;
+use crate::error::OpenAIError;
+use crate::retry::{RetryMode, RetryPolicy};
+use crate::schema::FunctionParameters;
+use crate::sse;
+use crate::tools::{ToolRegistry, ToolRun};
+use crate::image;
+use crate::batch::{BatchInput, BatchResults, BATCH_CHAT_ENDPOINT};
+use crate::upload::FileUpload;
+use crate::types::{ChatCompletionRequest, ChatCompletionResponse, ResponseFormat, StreamResponse, TranscriptionRequest, TranscriptionResponse, TranslationRequest, TranslationResponse, ImageRequest, ImageEditRequest, ImageVariationRequest, ImageResponse, Image, ImageData, decode_image, SpeechRequest, EmbeddingRequest, EmbeddingResponse, ModerationInput, ModerationPart, ModerationRequest, ModerationResponse, Model, ModelList, DeletedModel, FilePurpose, FileObject, FileList, DeletedFile, Pagination, FineTuningJobRequest, FineTuningJob, FineTuningJobList, FineTuningJobEventList, FineTuningJobCheckpointList, BatchRequest, Batch, BatchList};
+use reqwest::{Client, Method, RequestBuilder, StatusCode, Response, multipart::Form, header::{HeaderMap, HeaderName, HeaderValue}};
 use bytes::Bytes;
-use std::path::Path;
-use serde::{Serialize, Deserialize};
+use futures::future;
+use futures::stream::{self, Stream, TryStreamExt};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use serde::{Serialize, de::DeserializeOwned};
 
 
-const CHAT_API_URL: &str = "https://api.openai.com/v1/chat/completions";
-const TRANSCRIPTIONS_API_URL: &str = "https://api.openai.com/v1/audio/transcriptions";
-const TRANSLATIONS_API_URL: &str = "https://api.openai.com/v1/audio/translations";
-const IMAGE_API_URL: &str = "https://api.openai.com/v1/images/generations";
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+const CHAT_API_PATH: &str = "/chat/completions";
+const TRANSCRIPTIONS_API_PATH: &str = "/audio/transcriptions";
+const TRANSLATIONS_API_PATH: &str = "/audio/translations";
+const SPEECH_API_PATH: &str = "/audio/speech";
+const IMAGE_API_PATH: &str = "/images/generations";
+const IMAGE_EDITS_API_PATH: &str = "/images/edits";
+const IMAGE_VARIATIONS_API_PATH: &str = "/images/variations";
+const EMBEDDINGS_API_PATH: &str = "/embeddings";
+const MODERATIONS_API_PATH: &str = "/moderations";
+const MODELS_API_PATH: &str = "/models";
+const FILES_API_PATH: &str = "/files";
+const FINE_TUNING_API_PATH: &str = "/fine_tuning/jobs";
+const BATCHES_API_PATH: &str = "/batches";
+const OMNI_MODERATION_MODEL: &str = "omni-moderation-latest";
 
+#[derive(Clone)]
 pub struct OpenAIClient {
     client: reqwest::Client,
-    api_key: String,
+    api_key: Option<String>,
+    base_url: String,
+    headers: HeaderMap,
+    retry_policy: RetryPolicy,
+    moderation_guard: bool,
+}
+
+#[derive(Default)]
+pub struct OpenAIClientBuilder {
+    client: Option<Client>,
+    api_key: Option<String>,
+    base_url: Option<String>,
+    organization: Option<String>,
+    project: Option<String>,
+    headers: Vec<(String, String)>,
+    retry_policy: Option<RetryPolicy>,
+    moderation_guard: bool,
+}
+
+impl OpenAIClientBuilder {
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    pub fn organization(mut self, organization: impl Into<String>) -> Self {
+        self.organization = Some(organization.into());
+        self
+    }
+
+    pub fn project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn http_client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Moderates the user messages of every chat request and fails with `OpenAIError::Flagged`
+    /// instead of sending flagged content.
+    pub fn moderation_guard(mut self, enabled: bool) -> Self {
+        self.moderation_guard = enabled;
+        self
+    }
+
+    pub fn build(self) -> Result<OpenAIClient, OpenAIError> {
+        let base_url = self.base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+            return Err(OpenAIError::Validation(format!("Invalid base_url: {}. It should start with http:// or https://.", base_url)));
+        }
+
+        let mut headers = HeaderMap::new();
+        let named = self.organization.map(|value| ("OpenAI-Organization".to_string(), value)).into_iter()
+            .chain(self.project.map(|value| ("OpenAI-Project".to_string(), value)))
+            .chain(self.headers);
+        for (name, value) in named {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| OpenAIError::Validation(format!("Invalid header name: {}", name)))?;
+            let header_value = HeaderValue::from_str(&value)
+                .map_err(|_| OpenAIError::Validation(format!("Invalid value for header {}", name)))?;
+            headers.insert(header_name, header_value);
+        }
+
+        Ok(OpenAIClient {
+            client: self.client.unwrap_or_default(),
+            api_key: self.api_key,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            headers,
+            retry_policy: self.retry_policy.unwrap_or_default(),
+            moderation_guard: self.moderation_guard,
+        })
+    }
 }
 
 impl OpenAIClient {
     pub fn new(api_key: impl Into<String>) -> Self {
         Self {
             client: Client::new(),
-            api_key: api_key.into(),
+            api_key: Some(api_key.into()),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            headers: HeaderMap::new(),
+            retry_policy: RetryPolicy::default(),
+            moderation_guard: false,
         }
     }
 
-    pub async fn chat(&self, request: ChatCompletionRequest) -> Result<ChatCompletionResponse, Box<dyn Error + Send + Sync>> {
-        let response = self.build_request(CHAT_API_URL, &request)?.send().await?;
-        let text = response.text().await?;
-        Ok(serde_json::from_str(&text)?)
+    pub fn builder() -> OpenAIClientBuilder {
+        OpenAIClientBuilder::default()
     }
 
-    pub async fn chat_stream(&self, mut request: ChatCompletionRequest) -> Result<UnboundedReceiver<StreamResponse>, Box<dyn Error + Send + Sync>> {
+    pub async fn chat(&self, request: ChatCompletionRequest) -> Result<ChatCompletionResponse, OpenAIError> {
+        self.guard(&request).await?;
+        let response = self.send_json_request(CHAT_API_PATH, &request).await?;
+        Self::parse_response(response).await
+    }
+
+    pub async fn chat_stream(&self, mut request: ChatCompletionRequest) -> Result<impl Stream<Item = Result<StreamResponse, OpenAIError>> + Send + Unpin, OpenAIError> {
         request.stream = Some(true);
-        let request = self.build_request(CHAT_API_URL, &request)?;
-        let response = request.send().await?;
-        
-        let mut stream = response.bytes_stream();
-        let (tx, rx) = mpsc::unbounded_channel();
-        
-        tokio::spawn(async move {
-            while let Some(chunk) = stream.next().await {
-                match chunk {
-                    Ok(bytes) => {
-                        Self::process_chunk(bytes, &tx).await;
-                    },
-                    Err(e) => eprintln!("Error: {:?}", e),
-                }
+        self.guard(&request).await?;
+        let response = self.send_json_request(CHAT_API_PATH, &request).await?;
+        let response = Self::check_status(response).await?;
+
+        Ok(sse::json_event_stream(response))
+    }
+
+    pub async fn chat_typed<T: FunctionParameters>(&self, mut request: ChatCompletionRequest) -> Result<T, OpenAIError> {
+        request.response_format = Some(ResponseFormat::json_schema::<T>()?);
+        let response = self.chat(request).await?;
+        let message = response.choices.into_iter().next()
+            .map(|choice| choice.message)
+            .ok_or_else(|| OpenAIError::Validation("Response contains no choices".to_string()))?;
+
+        if let Some(refusal) = message.refusal {
+            return Err(OpenAIError::Refusal(refusal));
+        }
+        let content = message.content.unwrap_or_default();
+        serde_json::from_str(&content).map_err(|source| OpenAIError::SchemaMismatch { source, content })
+    }
+
+    pub async fn run_with_tools(&self, request: ChatCompletionRequest, registry: &ToolRegistry) -> Result<ToolRun, OpenAIError> {
+        registry.run(self, request).await
+    }
+
+    pub async fn transcription(&self, request: TranscriptionRequest) -> Result<TranscriptionResponse, OpenAIError> {
+        let file = FileUpload::from_path(&request.file)?;
+        self.send_multipart_request(TRANSCRIPTIONS_API_PATH, RetryMode::Idempotent, || async {
+            let file_part = file.part().await?;
+            let mut form = Form::new().part("file", file_part).text("model", request.model.clone());
+
+            if let Some(prompt) = &request.prompt { form = form.text("prompt", prompt.clone()); }
+            if let Some(response_format) = &request.response_format { form = form.text("response_format", response_format.clone()); }
+            if let Some(temperature) = request.temperature { form = form.text("temperature", temperature.to_string()); }
+            if let Some(language) = &request.language { form = form.text("language", language.clone()); }
+
+            Ok(form)
+        }).await
+    }
+
+    pub async fn translation(&self, request: TranslationRequest) -> Result<TranslationResponse, OpenAIError> {
+        let file = FileUpload::from_path(&request.file)?;
+        self.send_multipart_request(TRANSLATIONS_API_PATH, RetryMode::Idempotent, || async {
+            let file_part = file.part().await?;
+            let mut form = Form::new().part("file", file_part).text("model", request.model.clone());
+
+            if let Some(prompt) = &request.prompt { form = form.text("prompt", prompt.clone()); }
+            if let Some(response_format) = &request.response_format { form = form.text("response_format", response_format.clone()); }
+            if let Some(temperature) = request.temperature { form = form.text("temperature", temperature.to_string()); }
+
+            Ok(form)
+        }).await
+    }
+
+    pub async fn speech(&self, request: SpeechRequest) -> Result<Bytes, OpenAIError> {
+        let response = self.send_json_request(SPEECH_API_PATH, &request).await?;
+        Ok(Self::check_status(response).await?.bytes().await?)
+    }
+
+    /// Streams the audio as it is synthesized, so playback can start before the whole input is spoken.
+    pub async fn speech_stream(&self, request: SpeechRequest) -> Result<impl Stream<Item = Result<Bytes, OpenAIError>> + Send + Unpin, OpenAIError> {
+        let response = self.send_json_request(SPEECH_API_PATH, &request).await?;
+        let response = Self::check_status(response).await?;
+
+        Ok(response.bytes_stream().map_err(OpenAIError::from))
+    }
+
+    /// Writes the audio to `writer` chunk by chunk and returns the number of bytes written.
+    pub async fn speech_to_writer<W: AsyncWrite + Unpin>(&self, request: SpeechRequest, writer: &mut W) -> Result<u64, OpenAIError> {
+        let mut audio = self.speech_stream(request).await?;
+        let mut written = 0;
+        while let Some(chunk) = audio.try_next().await? {
+            writer.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+
+    pub async fn image(&self, request: ImageRequest) -> Result<ImageResponse, OpenAIError> {
+        let response = self.send_json_request(IMAGE_API_PATH, &request).await?;
+        Self::parse_response(response).await
+    }
+
+    pub async fn image_edit(&self, request: ImageEditRequest) -> Result<ImageResponse, OpenAIError> {
+        let (image, image_info) = image::read_square_png(&request.image).await?;
+        let mask = match &request.mask {
+            Some(mask) => {
+                let (mask, mask_info) = image::read_square_png(mask).await?;
+                image::check_mask(&image_info, &mask_info)?;
+                Some(mask)
             }
-        });
-    
-        Ok(rx)
+            None => None,
+        };
+
+        self.send_multipart_request(IMAGE_EDITS_API_PATH, RetryMode::Idempotent, || async {
+            let mut form = Form::new().part("image", image.part().await?).text("prompt", request.prompt.clone());
+
+            if let Some(mask) = &mask { form = form.part("mask", mask.part().await?); }
+            if let Some(n) = request.n { form = form.text("n", n.to_string()); }
+            if let Some(size) = request.size { form = form.text("size", size.to_string()); }
+            if let Some(response_format) = &request.response_format { form = form.text("response_format", response_format.clone()); }
+            if let Some(user) = &request.user { form = form.text("user", user.clone()); }
+
+            Ok(form)
+        }).await
     }
 
-    pub async fn transcription(&self, request: TranscriptionRequest) -> Result<TranscriptionResponse, Box<dyn Error + Send + Sync>> {
-        let file_part = self.create_file_part(&request.file).await?;
-        let mut form = Form::new().part("file", file_part).text("model", request.model);
-        
-        if let Some(prompt) = request.prompt { form = form.text("prompt", prompt); }
-        if let Some(response_format) = request.response_format { form = form.text("response_format", response_format); }
-        if let Some(temperature) = request.temperature { form = form.text("temperature", temperature.to_string()); }
-        if let Some(language) = request.language { form = form.text("language", language); }
+    pub async fn image_variation(&self, request: ImageVariationRequest) -> Result<ImageResponse, OpenAIError> {
+        let (image, _) = image::read_square_png(&request.image).await?;
+
+        self.send_multipart_request(IMAGE_VARIATIONS_API_PATH, RetryMode::Idempotent, || async {
+            let mut form = Form::new().part("image", image.part().await?);
 
-        self.send_multipart_request(TRANSCRIPTIONS_API_URL, form).await
+            if let Some(n) = request.n { form = form.text("n", n.to_string()); }
+            if let Some(size) = request.size { form = form.text("size", size.to_string()); }
+            if let Some(response_format) = &request.response_format { form = form.text("response_format", response_format.clone()); }
+            if let Some(user) = &request.user { form = form.text("user", user.clone()); }
+
+            Ok(form)
+        }).await
+    }
+
+    /// Returns the bytes of a generated image, decoding base64 data or downloading the URL.
+    pub async fn image_bytes(&self, image: &Image) -> Result<Bytes, OpenAIError> {
+        let url = match &image.data {
+            ImageData::Url { url } => url,
+            ImageData::Base64 { b64_json } => return decode_image(b64_json).map(Bytes::from),
+        };
+        // Image URLs point to storage outside the API, so the request is sent without the API key.
+        let response = self.client.get(url).send().await?;
+        Ok(Self::check_status(response).await?.bytes().await?)
     }
 
-    pub async fn translation(&self, request: TranslationRequest) -> Result<TranslationResponse, Box<dyn Error + Send + Sync>> {
-        let file_part = self.create_file_part(&request.file).await?;
-        let mut form = Form::new().part("file", file_part).text("model", request.model);
-        
-        if let Some(prompt) = request.prompt { form = form.text("prompt", prompt); }
-        if let Some(response_format) = request.response_format { form = form.text("response_format", response_format); }
-        if let Some(temperature) = request.temperature { form = form.text("temperature", temperature.to_string()); }
+    /// Writes every image of a response to `dir` as `image-{created}-{index}.{extension}`, fetching
+    /// them concurrently. Returns the paths in the order of the response.
+    pub async fn save_images(&self, response: &ImageResponse, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, OpenAIError> {
+        let dir = dir.as_ref();
+        tokio::fs::create_dir_all(dir).await?;
 
-        self.send_multipart_request(TRANSLATIONS_API_URL, form).await
+        future::try_join_all(response.data.iter().enumerate().map(|(index, data)| async move {
+            let bytes = self.image_bytes(data).await?;
+            let path = dir.join(image::file_name(response.created, index, &bytes));
+            tokio::fs::write(&path, &bytes).await?;
+            Ok(path)
+        })).await
     }
 
-    pub async fn image(&self, request: ImageRequest) -> Result<ImageResponse, Box<dyn Error + Send + Sync>> {
-        let response = self.build_request(IMAGE_API_URL, &request)?.send().await?;
-        let text = response.text().await?;
-        Ok(serde_json::from_str(&text)?)
+    pub async fn embeddings(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse, OpenAIError> {
+        let response = self.send_json_request(EMBEDDINGS_API_PATH, &request).await?;
+        Self::parse_response(response).await
     }
 
-    fn build_request<T: Serialize>(&self, url: &str, request: &T) -> Result<RequestBuilder, Box<dyn Error + Send + Sync>> {
-        Ok(self.client.post(url)
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(request))
-    }
-
-    async fn send_multipart_request<R: for<'de> Deserialize<'de>>(&self, url: &str, form: Form) -> Result<R, Box<dyn Error + Send + Sync>> {
-        let response = self.client.post(url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .multipart(form)
-            .send()
-            .await?;
-        let text = response.text().await?;
-        Ok(serde_json::from_str(&text)?)
-    }
-
-    async fn process_chunk(chunk: Bytes, tx: &UnboundedSender<StreamResponse>) {
-        for line in String::from_utf8_lossy(&chunk).split('\n').filter(|s| !s.is_empty() && s.contains('{')) {
-            if let Some(start) = line.find('{') {
-                let json_str = &line[start..];
-                match serde_json::from_str::<StreamResponse>(json_str.trim()) {
-                    Ok(parsed_obj) => {
-                        if let Err(_) = tx.send(parsed_obj) {
-                            eprintln!("Error sending parsed object through channel");
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("JSON deserialize error: {:?}", e);
-                    }
+    pub async fn moderation(&self, request: ModerationRequest) -> Result<ModerationResponse, OpenAIError> {
+        let response = self.send_json_request(MODERATIONS_API_PATH, &request).await?;
+        Self::parse_response(response).await
+    }
+
+    pub async fn list_models(&self) -> Result<ModelList, OpenAIError> {
+        self.send_request(Method::GET, MODELS_API_PATH).await
+    }
+
+    pub async fn retrieve_model(&self, model: &str) -> Result<Model, OpenAIError> {
+        self.send_request(Method::GET, &format!("{}/{}", MODELS_API_PATH, model)).await
+    }
+
+    pub async fn delete_model(&self, model: &str) -> Result<DeletedModel, OpenAIError> {
+        self.send_request(Method::DELETE, &format!("{}/{}", MODELS_API_PATH, model)).await
+    }
+
+    /// Checks that the model of a request is available to this API key, e.g. before starting a long job.
+    pub async fn validate_model(&self, request: &ChatCompletionRequest) -> Result<Model, OpenAIError> {
+        match self.retrieve_model(&request.model).await {
+            Err(e) if e.status() == Some(StatusCode::NOT_FOUND) => {
+                Err(OpenAIError::Validation(format!("Invalid model: {}. It is not available to this API key.", request.model)))
+            }
+            result => result,
+        }
+    }
+
+    pub async fn upload_file(&self, file: FileUpload, purpose: FilePurpose) -> Result<FileObject, OpenAIError> {
+        // A reader is consumed by the first attempt and cannot be replayed.
+        let mode = if file.is_repeatable() { RetryMode::Unprocessed } else { RetryMode::Never };
+        self.send_multipart_request(FILES_API_PATH, mode, || async {
+            Ok(Form::new().part("file", file.part().await?).text("purpose", purpose.to_string()))
+        }).await
+    }
+
+    pub async fn list_files(&self, purpose: Option<FilePurpose>) -> Result<FileList, OpenAIError> {
+        match purpose {
+            Some(purpose) => self.send_request(Method::GET, &format!("{}?purpose={}", FILES_API_PATH, purpose)).await,
+            None => self.send_request(Method::GET, FILES_API_PATH).await,
+        }
+    }
+
+    pub async fn retrieve_file(&self, file_id: &str) -> Result<FileObject, OpenAIError> {
+        self.send_request(Method::GET, &format!("{}/{}", FILES_API_PATH, file_id)).await
+    }
+
+    pub async fn delete_file(&self, file_id: &str) -> Result<DeletedFile, OpenAIError> {
+        self.send_request(Method::DELETE, &format!("{}/{}", FILES_API_PATH, file_id)).await
+    }
+
+    /// Streams the content of a file without buffering it, e.g. to copy batch results to disk.
+    pub async fn file_content(&self, file_id: &str) -> Result<impl Stream<Item = Result<Bytes, OpenAIError>> + Send + Unpin, OpenAIError> {
+        let path = format!("{}/{}/content", FILES_API_PATH, file_id);
+        let response = self.send_with_retry(|| async { Ok(self.request(Method::GET, &path)) }).await?;
+        let response = Self::check_status(response).await?;
+
+        Ok(response.bytes_stream().map_err(OpenAIError::from))
+    }
+
+    pub async fn create_fine_tuning_job(&self, request: FineTuningJobRequest) -> Result<FineTuningJob, OpenAIError> {
+        let response = self.send_create_request(FINE_TUNING_API_PATH, &request).await?;
+        Self::parse_response(response).await
+    }
+
+    pub async fn list_fine_tuning_jobs(&self, pagination: &Pagination) -> Result<FineTuningJobList, OpenAIError> {
+        self.send_request(Method::GET, &format!("{}{}", FINE_TUNING_API_PATH, pagination.query())).await
+    }
+
+    pub async fn retrieve_fine_tuning_job(&self, job_id: &str) -> Result<FineTuningJob, OpenAIError> {
+        self.send_request(Method::GET, &format!("{}/{}", FINE_TUNING_API_PATH, job_id)).await
+    }
+
+    pub async fn cancel_fine_tuning_job(&self, job_id: &str) -> Result<FineTuningJob, OpenAIError> {
+        self.send_request(Method::POST, &format!("{}/{}/cancel", FINE_TUNING_API_PATH, job_id)).await
+    }
+
+    pub async fn list_fine_tuning_events(&self, job_id: &str, pagination: &Pagination) -> Result<FineTuningJobEventList, OpenAIError> {
+        self.send_request(Method::GET, &format!("{}/{}/events{}", FINE_TUNING_API_PATH, job_id, pagination.query())).await
+    }
+
+    pub async fn list_fine_tuning_checkpoints(&self, job_id: &str, pagination: &Pagination) -> Result<FineTuningJobCheckpointList, OpenAIError> {
+        self.send_request(Method::GET, &format!("{}/{}/checkpoints{}", FINE_TUNING_API_PATH, job_id, pagination.query())).await
+    }
+
+    /// Polls a job every `interval` and yields it whenever its status changes. The stream ends after
+    /// the job succeeds, fails or is cancelled, or after the first error.
+    pub fn wait_for_fine_tune(&self, job_id: impl Into<String>, interval: Duration) -> impl Stream<Item = Result<FineTuningJob, OpenAIError>> + Send + Unpin {
+        let (client, job_id) = (self.clone(), job_id.into());
+        poll_status(interval, move || {
+            let (client, job_id) = (client.clone(), job_id.clone());
+            async move { client.retrieve_fine_tuning_job(&job_id).await }
+        }, |job| (job.status, job.status.is_terminal()))
+    }
+
+    pub async fn create_batch(&self, request: BatchRequest) -> Result<Batch, OpenAIError> {
+        let response = self.send_create_request(BATCHES_API_PATH, &request).await?;
+        Self::parse_response(response).await
+    }
+
+    /// Uploads the input file and creates a chat completions batch from it.
+    pub async fn submit_batch(&self, input: BatchInput, metadata: Option<HashMap<String, String>>) -> Result<Batch, OpenAIError> {
+        if input.is_empty() {
+            return Err(OpenAIError::Validation("Invalid batch: it should contain at least one request.".to_string()));
+        }
+        let file = self.upload_file(input.into_upload("batch.jsonl"), FilePurpose::Batch).await?;
+        let request = BatchRequest { input_file_id: file.id, endpoint: BATCH_CHAT_ENDPOINT.to_string(), completion_window: "24h".to_string(), metadata };
+        self.create_batch(request).await
+    }
+
+    pub async fn retrieve_batch(&self, batch_id: &str) -> Result<Batch, OpenAIError> {
+        self.send_request(Method::GET, &format!("{}/{}", BATCHES_API_PATH, batch_id)).await
+    }
+
+    pub async fn cancel_batch(&self, batch_id: &str) -> Result<Batch, OpenAIError> {
+        self.send_request(Method::POST, &format!("{}/{}/cancel", BATCHES_API_PATH, batch_id)).await
+    }
+
+    pub async fn list_batches(&self, pagination: &Pagination) -> Result<BatchList, OpenAIError> {
+        self.send_request(Method::GET, &format!("{}{}", BATCHES_API_PATH, pagination.query())).await
+    }
+
+    /// Polls a batch every `interval` and yields it whenever its status changes, until it completes,
+    /// fails, expires or is cancelled.
+    pub fn wait_for_batch(&self, batch_id: impl Into<String>, interval: Duration) -> impl Stream<Item = Result<Batch, OpenAIError>> + Send + Unpin {
+        let (client, batch_id) = (self.clone(), batch_id.into());
+        poll_status(interval, move || {
+            let (client, batch_id) = (client.clone(), batch_id.clone());
+            async move { client.retrieve_batch(&batch_id).await }
+        }, |batch| (batch.status, batch.status.is_terminal()))
+    }
+
+    /// Downloads the output and error files of a batch and parses them by custom_id.
+    pub async fn batch_results(&self, batch: &Batch) -> Result<BatchResults, OpenAIError> {
+        let mut results = BatchResults::default();
+        for file_id in batch.output_file_id.iter().chain(&batch.error_file_id) {
+            results.extend_from_stream(self.file_content(file_id).await?).await?;
+        }
+        Ok(results)
+    }
+
+    async fn guard(&self, request: &ChatCompletionRequest) -> Result<(), OpenAIError> {
+        if !self.moderation_guard {
+            return Ok(());
+        }
+        // Images are only accepted by omni-moderation models, so text-only requests keep the default.
+        let parts = request.user_moderation_input();
+        let moderation_request = if parts.iter().any(|part| matches!(part, ModerationPart::ImageUrl { .. })) {
+            ModerationRequest { input: ModerationInput::Parts(parts), model: Some(OMNI_MODERATION_MODEL.to_string()) }
+        } else if !parts.is_empty() {
+            ModerationRequest { input: ModerationInput::TextArray(request.user_text()), model: None }
+        } else {
+            return Ok(());
+        };
+
+        let response = self.moderation(moderation_request).await?;
+        let mut categories: Vec<String> = Vec::new();
+        for result in response.results.iter().filter(|result| result.flagged) {
+            for category in result.categories.flagged() {
+                if !categories.iter().any(|flagged| flagged == category) {
+                    categories.push(category.to_string());
                 }
-            } else {
-                eprintln!("No valid JSON found in chunk");
             }
         }
+
+        if response.results.iter().any(|result| result.flagged) {
+            return Err(OpenAIError::Flagged { categories });
+        }
+        Ok(())
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.client.request(method, format!("{}{}", self.base_url, path))
+            .headers(self.headers.clone());
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+
+    fn post(&self, path: &str) -> RequestBuilder {
+        self.request(Method::POST, path)
+    }
+
+    fn build_request<T: Serialize>(&self, path: &str, request: &T) -> RequestBuilder {
+        self.post(path)
+            .header("Content-Type", "application/json")
+            .json(request)
+    }
+
+    async fn send_request<R: DeserializeOwned>(&self, method: Method, path: &str) -> Result<R, OpenAIError> {
+        let response = self.send_with_retry(|| async { Ok(self.request(method.clone(), path)) }).await?;
+        Self::parse_response(response).await
     }
 
-    async fn create_file_part<P: AsRef<Path>>(&self, path: P) -> Result<Part, Box<dyn Error + Send + Sync>> {
-        let file_name = path.as_ref()
-            .file_name()
-            .ok_or("Invalid file name")?
-            .to_str()
-            .ok_or("Non UTF-8 file name")?
-            .to_string();
+    async fn send_json_request<T: Serialize>(&self, path: &str, request: &T) -> Result<Response, OpenAIError> {
+        self.send_with_retry(|| async { Ok(self.build_request(path, request)) }).await
+    }
 
-        Ok(Part::stream(self.file_stream_body(path).await?)
-            .file_name(file_name)
-            .mime_str("application/octet-stream")?)
+    // Sends a request that creates a resource, retrying only failures it was not processed after.
+    async fn send_create_request<T: Serialize>(&self, path: &str, request: &T) -> Result<Response, OpenAIError> {
+        self.send_with_mode(RetryMode::Unprocessed, || async { Ok(self.build_request(path, request)) }).await
     }
 
-    async fn file_stream_body<P: AsRef<Path>>(&self, path: P) -> Result<Body, Box<dyn Error + Send + Sync>> {
-        let file = tokio::fs::File::open(path).await?;
-        let stream = FramedRead::new(file, BytesCodec::new());
-        Ok(Body::wrap_stream(stream))
+    // Multipart bodies stream the file and cannot be cloned, so the form is rebuilt for every attempt.
+    async fn send_multipart_request<R, F, Fut>(&self, path: &str, mode: RetryMode, mut build_form: F) -> Result<R, OpenAIError>
+    where
+        R: DeserializeOwned,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<Form, OpenAIError>>,
+    {
+        let response = self.send_with_mode(mode, || {
+            let form = build_form();
+            async move { Ok(self.post(path).multipart(form.await?)) }
+        }).await?;
+        Self::parse_response(response).await
     }
-}
\ No newline at end of file
+
+    async fn send_with_retry<F, Fut>(&self, build: F) -> Result<Response, OpenAIError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<RequestBuilder, OpenAIError>>,
+    {
+        self.send_with_mode(RetryMode::Idempotent, build).await
+    }
+
+    async fn send_with_mode<F, Fut>(&self, mode: RetryMode, mut build: F) -> Result<Response, OpenAIError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<RequestBuilder, OpenAIError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            let result = build().await?.send().await;
+            let retry = match &result {
+                Ok(response) => RetryPolicy::should_retry_status(response.status(), response.headers(), mode),
+                Err(e) => RetryPolicy::should_retry_error(e, mode),
+            };
+            if !retry || attempt >= self.retry_policy.max_attempts {
+                return Ok(result?);
+            }
+
+            let headers = result.as_ref().ok().map(|response| response.headers());
+            tokio::time::sleep(self.retry_policy.delay(attempt, headers)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn check_status(response: Response) -> Result<Response, OpenAIError> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().await?;
+        Err(OpenAIError::from_response(status, body))
+    }
+
+    async fn parse_response<R: DeserializeOwned>(response: Response) -> Result<R, OpenAIError> {
+        let body = Self::check_status(response).await?.text().await?;
+        serde_json::from_str(&body).map_err(|source| OpenAIError::Deserialize { source, body })
+    }
+}
+
+// Calls `fetch` every `interval` and yields the result whenever the status returned by `status`
+// changes. The stream ends once `status` reports a terminal state or `fetch` fails.
+fn poll_status<T, S, F, Fut>(interval: Duration, fetch: F, status: fn(&T) -> (S, bool)) -> impl Stream<Item = Result<T, OpenAIError>> + Send + Unpin
+where
+    T: Send + 'static,
+    S: PartialEq + Send + 'static,
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<T, OpenAIError>> + Send,
+{
+    let state = (fetch, None::<S>, false);
+
+    Box::pin(stream::unfold(state, move |(fetch, last_status, done)| async move {
+        if done {
+            return None;
+        }
+        loop {
+            if last_status.is_some() {
+                tokio::time::sleep(interval).await;
+            }
+            match fetch().await {
+                Err(e) => return Some((Err(e), (fetch, last_status, true))),
+                Ok(item) => {
+                    let (current, terminal) = status(&item);
+                    if last_status.as_ref() != Some(&current) {
+                        return Some((Ok(item), (fetch, Some(current), terminal)));
+                    }
+                }
+            }
+        }
+    }))
+}
@@ -0,0 +1,393 @@
+use crate::error::OpenAIError;
+use crate::types::{ContentPart, Function, MessageContent, MessageRequest, Role, Tool};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+// Training context length and price per million training tokens of the fine-tunable models,
+// matched by prefix so dated snapshots and `ft:` models resolve to their base model.
+const FINE_TUNE_MODELS: [(&str, usize, f64); 8] = [
+    ("gpt-4o-mini", 65536, 3.0),
+    ("gpt-4o", 65536, 25.0),
+    ("gpt-4.1-nano", 65536, 1.5),
+    ("gpt-4.1-mini", 65536, 5.0),
+    ("gpt-4.1", 65536, 25.0),
+    ("gpt-3.5-turbo", 16385, 8.0),
+    ("davinci-002", 16384, 6.0),
+    ("babbage-002", 16384, 0.4),
+];
+
+// Base models fine-tuned on `{"prompt", "completion"}` lines instead of chat messages.
+const COMPLETION_MODELS: [&str; 2] = ["davinci-002", "babbage-002"];
+
+const TOKENS_PER_MESSAGE: usize = 4;
+const TOKENS_PER_IMAGE: usize = 85;
+const TOKENS_PER_REPLY: usize = 3;
+
+#[derive(Deserialize)]
+struct TrainingExample {
+    messages: Vec<MessageRequest>,
+    #[serde(default)]
+    tools: Vec<Tool>,
+    #[serde(default)]
+    functions: Vec<Function>,
+}
+
+#[derive(Deserialize)]
+struct CompletionExample {
+    prompt: String,
+    completion: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetIssue {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for DatasetIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DatasetReport {
+    pub examples: usize,
+    pub issues: Vec<DatasetIssue>,
+    pub total_tokens: usize,
+    pub max_example_tokens: usize,
+    pub context_length: Option<usize>,
+    pub estimated_cost_per_epoch: Option<f64>,
+}
+
+impl DatasetReport {
+    pub fn is_valid(&self) -> bool {
+        self.examples > 0 && self.issues.is_empty()
+    }
+}
+
+/// Rough token count of a text, about four characters per token for English.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Checks a fine-tuning JSONL dataset offline and estimates its size for `model`. Datasets are
+/// chat examples, or prompt/completion pairs for davinci-002 and babbage-002. Unknown models
+/// skip the context length check and the cost estimate.
+pub fn validate_dataset(jsonl: &str, model: &str) -> DatasetReport {
+    let base_model = model.strip_prefix("ft:").unwrap_or(model);
+    let limits = FINE_TUNE_MODELS.iter().find(|(name, _, _)| base_model.starts_with(name));
+    let completion = COMPLETION_MODELS.iter().any(|name| base_model.starts_with(name));
+    let mut report = DatasetReport { context_length: limits.map(|(_, context_length, _)| *context_length), ..Default::default() };
+
+    for (index, line) in jsonl.lines().enumerate() {
+        let (tokens, issues) = check_line(line, completion, report.context_length, model);
+        report.issues.extend(issues.into_iter().map(|message| DatasetIssue { line: index + 1, message }));
+
+        if let Some(tokens) = tokens {
+            report.examples += 1;
+            report.total_tokens += tokens;
+            report.max_example_tokens = report.max_example_tokens.max(tokens);
+        }
+    }
+
+    report.estimated_cost_per_epoch = limits.map(|(_, _, price)| report.total_tokens as f64 / 1_000_000.0 * price);
+    report
+}
+
+pub async fn validate_dataset_file(path: impl AsRef<Path>, model: &str) -> Result<DatasetReport, OpenAIError> {
+    let jsonl = tokio::fs::read_to_string(path).await?;
+    Ok(validate_dataset(&jsonl, model))
+}
+
+// Returns the estimated tokens of the example, if the line parses, and its issues.
+fn check_line(line: &str, completion: bool, context_length: Option<usize>, model: &str) -> (Option<usize>, Vec<String>) {
+    if line.trim().is_empty() {
+        return (None, vec!["Empty line".to_string()]);
+    }
+    let parsed = if completion {
+        serde_json::from_str(line).map(|example| check_completion(&example))
+    } else {
+        serde_json::from_str(line).map(|example| (check_example(&example), example_tokens(&example)))
+    };
+    let (mut issues, tokens) = match parsed {
+        Ok(checked) => checked,
+        Err(e) => return (None, vec![format!("Invalid example: {}", e)]),
+    };
+
+    if let Some(context_length) = context_length {
+        if tokens > context_length {
+            issues.push(format!("Example has about {} tokens. It should have at most {} for {}.", tokens, context_length, model));
+        }
+    }
+    (Some(tokens), issues)
+}
+
+fn check_example(example: &TrainingExample) -> Vec<String> {
+    let mut issues = Vec::new();
+    if example.messages.is_empty() {
+        issues.push("Example has no messages".to_string());
+        return issues;
+    }
+
+    let tool_names: HashSet<&str> = example.tools.iter().map(|tool| tool.function.name.as_str())
+        .chain(example.functions.iter().map(|function| function.name.as_str()))
+        .collect();
+    let mut previous: Option<&Role> = None;
+    let mut pending_tool_calls: Vec<&str> = Vec::new();
+    let mut pending_function: Option<&str> = None;
+
+    for (index, message) in example.messages.iter().enumerate() {
+        let position = index + 1;
+        let awaiting_results = !pending_tool_calls.is_empty() || pending_function.is_some();
+        let allowed = match (previous, &message.role) {
+            (_, Role::Tool) => !pending_tool_calls.is_empty(),
+            (_, Role::Function) => pending_function.is_some(),
+            _ if awaiting_results => false,
+            (None, Role::System | Role::User) => true,
+            (Some(Role::System), Role::User) => true,
+            (Some(Role::User | Role::Tool | Role::Function), Role::Assistant) => true,
+            (Some(Role::Assistant), Role::User) => true,
+            _ => false,
+        };
+        if !allowed {
+            let after = previous.map_or("the start".to_string(), |role| format!("{:?}", role));
+            issues.push(format!("Message {}: unexpected {:?} message after {}", position, message.role, after));
+        }
+
+        let has_content = match &message.content {
+            Some(MessageContent::Text(text)) => !text.trim().is_empty(),
+            Some(MessageContent::Parts(parts)) => !parts.is_empty(),
+            None => false,
+        };
+
+        match &message.role {
+            Role::Assistant => {
+                let tool_calls = message.tool_calls.as_deref().unwrap_or_default();
+                if !has_content && tool_calls.is_empty() && message.function_call.is_none() {
+                    issues.push(format!("Message {}: assistant message has no content, tool calls or function call", position));
+                }
+                for tool_call in tool_calls {
+                    if tool_call.id.is_empty() {
+                        issues.push(format!("Message {}: tool call has no id", position));
+                    } else if pending_tool_calls.contains(&tool_call.id.as_str()) {
+                        issues.push(format!("Message {}: duplicate tool call id {}", position, tool_call.id));
+                    } else {
+                        pending_tool_calls.push(&tool_call.id);
+                    }
+                    issues.extend(check_call(position, &tool_call.function.name, &tool_call.function.arguments, &tool_names));
+                }
+                if let Some(function_call) = &message.function_call {
+                    issues.extend(check_call(position, &function_call.name, &function_call.arguments, &tool_names));
+                    pending_function = Some(&function_call.name);
+                }
+            }
+            Role::Tool => {
+                match message.tool_call_id.as_deref() {
+                    None => issues.push(format!("Message {}: tool message has no tool_call_id", position)),
+                    Some(id) if pending_tool_calls.contains(&id) => pending_tool_calls.retain(|pending| *pending != id),
+                    // Without pending calls the message was already reported as unexpected.
+                    Some(_) if pending_tool_calls.is_empty() => {}
+                    Some(id) => issues.push(format!("Message {}: tool_call_id {} does not match a preceding tool call", position, id)),
+                }
+                if !has_content {
+                    issues.push(format!("Message {}: tool message has no content", position));
+                }
+            }
+            Role::Function => {
+                if let Some(expected) = pending_function.take() {
+                    if message.name.as_deref() != Some(expected) {
+                        issues.push(format!("Message {}: function message name does not match the preceding function call", position));
+                    }
+                }
+                if !has_content {
+                    issues.push(format!("Message {}: function message has no content", position));
+                }
+            }
+            Role::System | Role::User => {
+                if !has_content {
+                    issues.push(format!("Message {}: {:?} message has no content", position, message.role));
+                }
+            }
+        }
+
+        previous = Some(&message.role);
+    }
+
+    if !pending_tool_calls.is_empty() || pending_function.is_some() {
+        issues.push("Example ends with unanswered tool calls".to_string());
+    }
+    if !matches!(example.messages.last().map(|message| &message.role), Some(Role::Assistant)) {
+        issues.push("Example should end with an assistant message".to_string());
+    }
+    issues
+}
+
+fn check_completion(example: &CompletionExample) -> (Vec<String>, usize) {
+    let mut issues = Vec::new();
+    if example.completion.trim().is_empty() {
+        issues.push("Example has an empty completion".to_string());
+    }
+    (issues, estimate_tokens(&example.prompt) + estimate_tokens(&example.completion))
+}
+
+fn check_call(position: usize, name: &str, arguments: &str, tool_names: &HashSet<&str>) -> Vec<String> {
+    let mut issues = Vec::new();
+    if name.is_empty() {
+        issues.push(format!("Message {}: call has no function name", position));
+    } else if !tool_names.is_empty() && !tool_names.contains(name) {
+        issues.push(format!("Message {}: call to {} which is not declared in tools or functions", position, name));
+    }
+    if serde_json::from_str::<serde_json::Value>(arguments).is_err() {
+        issues.push(format!("Message {}: arguments of {} are not valid JSON", position, name));
+    }
+    issues
+}
+
+fn example_tokens(example: &TrainingExample) -> usize {
+    let definitions = serde_json::to_string(&example.tools).unwrap_or_default().len()
+        + serde_json::to_string(&example.functions).unwrap_or_default().len();
+    let mut tokens = definitions.div_ceil(4) + TOKENS_PER_REPLY;
+
+    for message in &example.messages {
+        tokens += TOKENS_PER_MESSAGE;
+        tokens += match &message.content {
+            Some(MessageContent::Text(text)) => estimate_tokens(text),
+            Some(MessageContent::Parts(parts)) => parts.iter().map(|part| match part {
+                ContentPart::Text { text } => estimate_tokens(text),
+                _ => TOKENS_PER_IMAGE,
+            }).sum(),
+            None => 0,
+        };
+        tokens += message.name.as_deref().map_or(0, estimate_tokens);
+        if let Some(function_call) = &message.function_call {
+            tokens += estimate_tokens(&function_call.name) + estimate_tokens(&function_call.arguments);
+        }
+        for tool_call in message.tool_calls.iter().flatten() {
+            tokens += estimate_tokens(&tool_call.function.name) + estimate_tokens(&tool_call.function.arguments);
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues(example: serde_json::Value, model: &str) -> Vec<String> {
+        validate_dataset(&example.to_string(), model).issues.into_iter().map(|issue| issue.message).collect()
+    }
+
+    fn tool_call(id: &str) -> serde_json::Value {
+        serde_json::json!({ "id": id, "type": "function", "function": { "name": "get_weather", "arguments": "{\"city\":\"Paris\"}" } })
+    }
+
+    #[test]
+    fn accepts_parallel_tool_calls() {
+        let example = serde_json::json!({ "messages": [
+            { "role": "user", "content": "Weather in Paris and Rome?" },
+            { "role": "assistant", "tool_calls": [tool_call("call_1"), tool_call("call_2")] },
+            { "role": "tool", "tool_call_id": "call_2", "content": "18C" },
+            { "role": "tool", "tool_call_id": "call_1", "content": "21C" },
+            { "role": "assistant", "content": "Paris is 21C and Rome is 18C." },
+        ]});
+        assert_eq!(issues(example, "gpt-4o-mini"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_unanswered_tool_calls() {
+        let example = serde_json::json!({ "messages": [
+            { "role": "user", "content": "Weather in Paris?" },
+            { "role": "assistant", "tool_calls": [tool_call("call_1")] },
+        ]});
+        assert_eq!(issues(example, "gpt-4o-mini"), vec![
+            "Example ends with unanswered tool calls".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn reports_unknown_tool_call_id() {
+        let example = serde_json::json!({ "messages": [
+            { "role": "user", "content": "Weather in Paris?" },
+            { "role": "assistant", "tool_calls": [tool_call("call_1")] },
+            { "role": "tool", "tool_call_id": "call_9", "content": "21C" },
+            { "role": "tool", "tool_call_id": "call_1", "content": "21C" },
+            { "role": "assistant", "content": "It is 21C." },
+        ]});
+        assert_eq!(issues(example, "gpt-4o-mini"), vec![
+            "Message 3: tool_call_id call_9 does not match a preceding tool call".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn reports_unexpected_roles_once() {
+        let example = serde_json::json!({ "messages": [
+            { "role": "user", "content": "Hello" },
+            { "role": "user", "content": "Are you there?" },
+            { "role": "assistant", "content": "Yes." },
+        ]});
+        assert_eq!(issues(example, "gpt-4o-mini"), vec![
+            "Message 2: unexpected User message after User".to_string(),
+        ]);
+
+        let example = serde_json::json!({ "messages": [
+            { "role": "user", "content": "Hello" },
+            { "role": "function", "name": "get_weather", "content": "21C" },
+            { "role": "assistant", "content": "It is 21C." },
+        ]});
+        assert_eq!(issues(example, "gpt-4o-mini"), vec![
+            "Message 2: unexpected Function message after User".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn reports_examples_over_the_context_length() {
+        let example = serde_json::json!({ "messages": [
+            { "role": "user", "content": "a".repeat(16384 * 4) },
+            { "role": "assistant", "content": "Done." },
+        ]});
+        let report = validate_dataset(&example.to_string(), "gpt-3.5-turbo-0125");
+
+        assert_eq!(report.context_length, Some(16385));
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].message.contains("at most 16385 for gpt-3.5-turbo-0125"), "{}", report.issues[0]);
+    }
+
+    #[test]
+    fn validates_prompt_completion_pairs_for_base_models() {
+        let jsonl = [
+            serde_json::json!({ "prompt": "Capital of France ->", "completion": " Paris" }),
+            serde_json::json!({ "prompt": "Capital of Italy ->", "completion": " " }),
+            serde_json::json!({ "prompt": "a".repeat(16384 * 4), "completion": " Done" }),
+        ].map(|line| line.to_string()).join("\n");
+        let report = validate_dataset(&jsonl, "ft:babbage-002:acme::abc123");
+
+        assert_eq!(report.examples, 3);
+        let issues: Vec<String> = report.issues.iter().map(ToString::to_string).collect();
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0], "line 2: Example has an empty completion");
+        assert!(issues[1].starts_with("line 3: Example has about"), "{}", issues[1]);
+
+        let report = validate_dataset(&serde_json::json!({ "messages": [] }).to_string(), "davinci-002");
+        assert!(report.issues[0].message.contains("missing field `prompt`"), "{}", report.issues[0]);
+    }
+
+    #[test]
+    fn resolves_fine_tuned_models_to_their_base_model() {
+        let example = serde_json::json!({ "messages": [
+            { "role": "user", "content": "Hello" },
+            { "role": "assistant", "content": "Hi!" },
+        ]});
+        let report = validate_dataset(&example.to_string(), "ft:gpt-3.5-turbo-0125:acme::abc123");
+
+        assert!(report.is_valid());
+        assert_eq!(report.context_length, Some(16385));
+        assert!(report.estimated_cost_per_epoch.is_some());
+
+        let report = validate_dataset(&example.to_string(), "ft:unknown-model:acme::abc123");
+        assert_eq!(report.context_length, None);
+        assert_eq!(report.estimated_cost_per_epoch, None);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::tools::ToolRun;
+use derive_builder::UninitializedFieldError;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApiError {
+    pub message: String,
+    #[serde(rename = "type")]
+    pub error_type: Option<String>,
+    pub param: Option<String>,
+    pub code: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ApiErrorResponse {
+    error: ApiError,
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum OpenAIError {
+    Transport(reqwest::Error),
+    Http { status: StatusCode, body: String },
+    Api { status: StatusCode, error: ApiError },
+    Deserialize { source: serde_json::Error, body: String },
+    Validation(String),
+    Io(std::io::Error),
+    ToolIterationLimit { limit: usize, run: Box<ToolRun> },
+    Refusal(String),
+    SchemaMismatch { source: serde_json::Error, content: String },
+    Flagged { categories: Vec<String> },
+    TruncatedStream,
+}
+
+impl OpenAIError {
+    pub(crate) fn from_response(status: StatusCode, body: String) -> Self {
+        Self::from_error_body(status, &body).unwrap_or(OpenAIError::Http { status, body })
+    }
+
+    pub(crate) fn from_error_body(status: StatusCode, body: &str) -> Option<Self> {
+        serde_json::from_str::<ApiErrorResponse>(body)
+            .ok()
+            .map(|response| OpenAIError::Api { status, error: response.error })
+    }
+
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            OpenAIError::Http { status, .. } | OpenAIError::Api { status, .. } => Some(*status),
+            OpenAIError::Transport(e) => e.status(),
+            _ => None,
+        }
+    }
+
+    pub fn is_rate_limit(&self) -> bool {
+        self.status() == Some(StatusCode::TOO_MANY_REQUESTS)
+    }
+
+    pub fn is_auth(&self) -> bool {
+        matches!(self.status(), Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN))
+    }
+
+    pub fn is_bad_request(&self) -> bool {
+        matches!(self.status(), Some(StatusCode::BAD_REQUEST | StatusCode::NOT_FOUND | StatusCode::UNPROCESSABLE_ENTITY))
+    }
+
+    pub fn is_server_error(&self) -> bool {
+        matches!(self.status(), Some(status) if status.is_server_error())
+    }
+}
+
+impl fmt::Display for OpenAIError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenAIError::Transport(e) => write!(f, "Transport error: {}", e),
+            OpenAIError::Http { status, body } => write!(f, "HTTP error {}: {}", status, body),
+            OpenAIError::Api { status, error } => match &error.error_type {
+                Some(error_type) => write!(f, "OpenAI API error {} ({}): {}", status, error_type, error.message),
+                None => write!(f, "OpenAI API error {}: {}", status, error.message),
+            },
+            OpenAIError::Deserialize { source, body } => write!(f, "Failed to deserialize response: {}. Body: {}", source, body),
+            OpenAIError::Validation(message) => write!(f, "Validation error: {}", message),
+            OpenAIError::Io(e) => write!(f, "I/O error: {}", e),
+            OpenAIError::ToolIterationLimit { limit, .. } => write!(f, "The model was still calling functions after {} iterations", limit),
+            OpenAIError::Flagged { categories } => write!(f, "Request was flagged by moderation: {}", categories.join(", ")),
+            OpenAIError::Refusal(refusal) => write!(f, "The model refused to respond: {}", refusal),
+            OpenAIError::SchemaMismatch { source, content } => write!(f, "Response does not match the requested schema: {}. Content: {}", source, content),
+            OpenAIError::TruncatedStream => write!(f, "The event stream ended in the middle of an event"),
+        }
+    }
+}
+
+impl std::error::Error for OpenAIError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OpenAIError::Transport(e) => Some(e),
+            OpenAIError::Deserialize { source, .. } | OpenAIError::SchemaMismatch { source, .. } => Some(source),
+            OpenAIError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for OpenAIError {
+    fn from(e: reqwest::Error) -> Self {
+        OpenAIError::Transport(e)
+    }
+}
+
+impl From<std::io::Error> for OpenAIError {
+    fn from(e: std::io::Error) -> Self {
+        OpenAIError::Io(e)
+    }
+}
+
+impl From<UninitializedFieldError> for OpenAIError {
+    fn from(e: UninitializedFieldError) -> Self {
+        OpenAIError::Validation(e.to_string())
+    }
+}
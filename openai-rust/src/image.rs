@@ -0,0 +1,71 @@
+use crate::error::OpenAIError;
+use crate::types::image_mime_type;
+use crate::upload::FileUpload;
+use std::path::Path;
+
+const MAX_EDIT_IMAGE_BYTES: usize = 4 * 1024 * 1024;
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PngInfo {
+    pub width: u32,
+    pub height: u32,
+    pub has_alpha: bool,
+}
+
+// Reads the header of a PNG file: dimensions from IHDR and transparency from the color type
+// or a tRNS chunk before the image data.
+fn png_info(bytes: &[u8]) -> Option<PngInfo> {
+    if bytes.len() < 33 || bytes[..8] != PNG_SIGNATURE || &bytes[12..16] != b"IHDR" {
+        return None;
+    }
+    let read_u32 = |offset: usize| bytes.get(offset..offset + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    let width = read_u32(16)?;
+    let height = read_u32(20)?;
+    let mut has_alpha = matches!(bytes[25], 4 | 6);
+
+    let mut offset = 8;
+    while !has_alpha {
+        let (Some(length), Some(chunk_type)) = (read_u32(offset), bytes.get(offset + 4..offset + 8)) else { break };
+        match chunk_type {
+            b"tRNS" => has_alpha = true,
+            b"IDAT" | b"IEND" => break,
+            _ => offset += 12 + length as usize,
+        }
+    }
+
+    Some(PngInfo { width, height, has_alpha })
+}
+
+// Loads an image for the edits and variations endpoints, which accept square PNGs under 4 MB.
+pub(crate) async fn read_square_png(path: &str) -> Result<(FileUpload, PngInfo), OpenAIError> {
+    let bytes = tokio::fs::read(path).await?;
+    if bytes.len() >= MAX_EDIT_IMAGE_BYTES {
+        return Err(OpenAIError::Validation(format!("Invalid image: {} is {} bytes. It should be less than {} bytes.", path, bytes.len(), MAX_EDIT_IMAGE_BYTES)));
+    }
+    let info = png_info(&bytes)
+        .ok_or_else(|| OpenAIError::Validation(format!("Invalid image: {}. It should be a PNG file.", path)))?;
+    if info.width != info.height {
+        return Err(OpenAIError::Validation(format!("Invalid image: {} is {}x{}. It should be square.", path, info.width, info.height)));
+    }
+
+    let file_name = Path::new(path).file_name().and_then(|name| name.to_str()).unwrap_or("image.png");
+    Ok((FileUpload::from_bytes(bytes, file_name, "image/png"), info))
+}
+
+pub(crate) fn check_mask(image: &PngInfo, mask: &PngInfo) -> Result<(), OpenAIError> {
+    if (image.width, image.height) != (mask.width, mask.height) {
+        return Err(OpenAIError::Validation(format!("Invalid mask: {}x{}. It should have the same dimensions as the image ({}x{}).", mask.width, mask.height, image.width, image.height)));
+    }
+    if !mask.has_alpha {
+        return Err(OpenAIError::Validation("Invalid mask: it should have an alpha channel marking the areas to edit.".to_string()));
+    }
+    Ok(())
+}
+
+// Names a saved image after its response and position so that saving the same response twice
+// overwrites the same files.
+pub(crate) fn file_name(created: u32, index: usize, bytes: &[u8]) -> String {
+    let extension = image_mime_type(bytes).and_then(|mime_type| mime_type.strip_prefix("image/")).unwrap_or("png");
+    format!("image-{}-{}.{}", created, index, extension)
+}
@@ -1,4 +1,28 @@
+mod accumulator;
+mod batch;
 mod client;
+mod dataset;
+mod error;
+mod image;
+mod retry;
+mod schema;
+mod sse;
+mod tools;
+mod upload;
 pub mod types;
 
-pub use client::OpenAIClient;
\ No newline at end of file
+pub use accumulator::ChatStreamAccumulator;
+pub use batch::{BatchInput, BatchResults};
+pub use client::{OpenAIClient, OpenAIClientBuilder};
+pub use dataset::{estimate_tokens, validate_dataset, validate_dataset_file, DatasetIssue, DatasetReport};
+pub use error::{ApiError, OpenAIError};
+pub use retry::RetryPolicy;
+pub use schema::{FunctionParameters, SchemaProperty};
+pub use tools::{ToolCallRecord, ToolRegistry, ToolRun};
+pub use upload::FileUpload;
+
+#[cfg(feature = "derive")]
+pub use openai_rust_derive::FunctionParameters;
+
+#[doc(hidden)]
+pub use serde_json;
\ No newline at end of file
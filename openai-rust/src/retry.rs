@@ -0,0 +1,217 @@
+use rand::Rng;
+use reqwest::{StatusCode, header::{HeaderMap, RETRY_AFTER}};
+use std::time::{Duration, SystemTime};
+
+const MAX_HEADER_DELAY: Duration = Duration::from_secs(60);
+
+// Which failures a request may be retried after. Requests that create resources, such as files,
+// batches or fine-tuning jobs, are only retried when the server provably did not process them,
+// since a retry after a timeout or a 502 could create a duplicate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum RetryMode {
+    Idempotent,
+    Unprocessed,
+    Never,
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    pub(crate) fn should_retry_status(status: StatusCode, headers: &HeaderMap, mode: RetryMode) -> bool {
+        match mode {
+            RetryMode::Idempotent => matches!(status, StatusCode::REQUEST_TIMEOUT | StatusCode::CONFLICT | StatusCode::TOO_MANY_REQUESTS)
+                || status.is_server_error(),
+            RetryMode::Unprocessed => status == StatusCode::TOO_MANY_REQUESTS
+                || (status == StatusCode::SERVICE_UNAVAILABLE && headers.contains_key(RETRY_AFTER)),
+            RetryMode::Never => false,
+        }
+    }
+
+    pub(crate) fn should_retry_error(error: &reqwest::Error, mode: RetryMode) -> bool {
+        match mode {
+            RetryMode::Idempotent => error.is_connect() || error.is_timeout(),
+            RetryMode::Unprocessed => error.is_connect(),
+            RetryMode::Never => false,
+        }
+    }
+
+    pub(crate) fn delay(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        if let Some(delay) = headers.and_then(header_delay) {
+            return delay.min(MAX_HEADER_DELAY);
+        }
+
+        let exponent = attempt.saturating_sub(1).min(16);
+        let backoff = self.initial_backoff.saturating_mul(1 << exponent).min(self.max_backoff);
+        backoff.mul_f64(rand::thread_rng().gen_range(0.75..=1.0))
+    }
+}
+
+fn header_delay(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+    if let Some(ms) = header("retry-after-ms").and_then(|value| value.trim().parse::<f64>().ok()) {
+        return capped_seconds(ms / 1000.0);
+    }
+
+    if let Some(retry_after) = header("retry-after") {
+        if let Ok(seconds) = retry_after.trim().parse::<f64>() {
+            return capped_seconds(seconds);
+        }
+        if let Ok(date) = httpdate::parse_http_date(retry_after) {
+            return Some(date.duration_since(SystemTime::now()).unwrap_or_default());
+        }
+    }
+
+    ["x-ratelimit-reset-requests", "x-ratelimit-reset-tokens"]
+        .iter()
+        .filter_map(|name| header(name).and_then(parse_reset_duration))
+        .max()
+}
+
+// Parses the Go-style durations used by the rate limit headers, e.g. "1s", "6m0s" or "20ms".
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    let mut total = 0.0;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+
+    while !rest.is_empty() {
+        let number_end = rest.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+        let number: f64 = rest[..number_end].parse().ok()?;
+        rest = &rest[number_end..];
+        let unit_end = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let seconds = match &rest[..unit_end] {
+            "ms" => number / 1000.0,
+            "s" => number,
+            "m" => number * 60.0,
+            "h" => number * 3600.0,
+            _ => return None,
+        };
+        total += seconds;
+        rest = &rest[unit_end..];
+    }
+
+    capped_seconds(total)
+}
+
+// Header values are server-controlled, so they are clamped before the conversion, which panics
+// on values that are infinite or too large for a `Duration`.
+fn capped_seconds(seconds: f64) -> Option<Duration> {
+    if seconds.is_nan() {
+        return None;
+    }
+    Some(Duration::from_secs_f64(seconds.clamp(0.0, MAX_HEADER_DELAY.as_secs_f64())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn parses_go_style_reset_durations() {
+        assert_eq!(parse_reset_duration("6m0s"), Some(Duration::from_secs(60)));
+        assert_eq!(parse_reset_duration("20ms"), Some(Duration::from_millis(20)));
+        assert_eq!(parse_reset_duration("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_reset_duration("1m30s"), Some(Duration::from_secs(60)));
+        assert_eq!(parse_reset_duration(""), None);
+        assert_eq!(parse_reset_duration("5x"), None);
+    }
+
+    #[test]
+    fn reads_retry_after_seconds_and_http_dates() {
+        assert_eq!(header_delay(&headers(&[("retry-after", "2")])), Some(Duration::from_secs(2)));
+
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(30));
+        let delay = header_delay(&headers(&[("retry-after", &date)])).unwrap();
+        assert!(delay > Duration::from_secs(25) && delay <= Duration::from_secs(30), "{:?}", delay);
+
+        let past = httpdate::fmt_http_date(SystemTime::now() - Duration::from_secs(30));
+        assert_eq!(header_delay(&headers(&[("retry-after", &past)])), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn prefers_retry_after_ms() {
+        let headers = headers(&[("retry-after-ms", "250"), ("retry-after", "5"), ("x-ratelimit-reset-requests", "10s")]);
+        assert_eq!(header_delay(&headers), Some(Duration::from_millis(250)));
+        assert_eq!(RetryPolicy::default().delay(1, Some(&headers)), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn uses_longest_rate_limit_reset() {
+        let headers = headers(&[("x-ratelimit-reset-requests", "1s"), ("x-ratelimit-reset-tokens", "6m0s")]);
+        assert_eq!(header_delay(&headers), Some(MAX_HEADER_DELAY));
+
+        let headers = self::headers(&[("x-ratelimit-reset-requests", "1s"), ("x-ratelimit-reset-tokens", "20ms")]);
+        assert_eq!(header_delay(&headers), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn clamps_overflowing_and_infinite_values() {
+        for (name, value) in [
+            ("retry-after", "inf"),
+            ("retry-after", "1e20"),
+            ("retry-after-ms", "1e30"),
+            ("x-ratelimit-reset-requests", "99999999999999999999h"),
+        ] {
+            assert_eq!(header_delay(&headers(&[(name, value)])), Some(MAX_HEADER_DELAY), "{}: {}", name, value);
+        }
+
+        assert_eq!(header_delay(&headers(&[("retry-after", "-5")])), Some(Duration::ZERO));
+        assert_eq!(header_delay(&headers(&[("retry-after", "NaN")])), None);
+    }
+
+    #[test]
+    fn backs_off_exponentially_without_headers() {
+        let policy = RetryPolicy::default().initial_backoff(Duration::from_millis(100)).max_backoff(Duration::from_millis(300));
+
+        for (attempt, max) in [(1, 100), (2, 200), (3, 300), (10, 300)] {
+            let delay = policy.delay(attempt, None);
+            let max = Duration::from_millis(max);
+            assert!(delay >= max.mul_f64(0.75) && delay <= max, "attempt {}: {:?}", attempt, delay);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::error::OpenAIError;
+use crate::types::{Parameters, Property};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// Implemented by `#[derive(FunctionParameters)]` on structs whose fields are the arguments of a function.
+pub trait FunctionParameters: DeserializeOwned {
+    fn parameters() -> Parameters;
+}
+
+/// JSON Schema of a single value. Implemented for primitives, collections and by the derive macro.
+pub trait SchemaProperty {
+    fn property() -> Property;
+
+    fn is_optional() -> bool {
+        false
+    }
+}
+
+fn typed(param_type: &str) -> Property {
+    Property { param_type: Some(param_type.to_string()), ..Default::default() }
+}
+
+macro_rules! impl_schema_property {
+    ($param_type:literal => $($ty:ty),+) => {
+        $(
+            impl SchemaProperty for $ty {
+                fn property() -> Property {
+                    typed($param_type)
+                }
+            }
+        )+
+    };
+}
+
+impl_schema_property!("string" => String, str, char);
+impl_schema_property!("integer" => i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+impl_schema_property!("number" => f32, f64);
+impl_schema_property!("boolean" => bool);
+
+impl<T: SchemaProperty + ?Sized> SchemaProperty for &T {
+    fn property() -> Property {
+        T::property()
+    }
+}
+
+impl<T: SchemaProperty + ?Sized> SchemaProperty for Box<T> {
+    fn property() -> Property {
+        T::property()
+    }
+}
+
+impl<T: SchemaProperty> SchemaProperty for Option<T> {
+    fn property() -> Property {
+        Property { nullable: true, ..T::property() }
+    }
+
+    fn is_optional() -> bool {
+        true
+    }
+}
+
+impl<T: SchemaProperty> SchemaProperty for Vec<T> {
+    fn property() -> Property {
+        Property { items: Some(Box::new(T::property())), ..typed("array") }
+    }
+}
+
+impl<T: SchemaProperty> SchemaProperty for [T] {
+    fn property() -> Property {
+        Vec::<T>::property()
+    }
+}
+
+impl<T: SchemaProperty, S> SchemaProperty for HashMap<String, T, S> {
+    fn property() -> Property {
+        typed("object")
+    }
+}
+
+impl<T: SchemaProperty> SchemaProperty for BTreeMap<String, T> {
+    fn property() -> Property {
+        typed("object")
+    }
+}
+
+impl SchemaProperty for serde_json::Value {
+    fn property() -> Property {
+        Property::default()
+    }
+}
+
+// Adapts a schema to the subset accepted by strict structured outputs: objects reject unknown
+// properties and every property is required, with `Option` ones accepting `null` instead.
+// Fields that are only defaulted stay non-nullable, since serde does not default a `null`.
+// Maps and `serde_json::Value` have no declared properties and cannot be made strict.
+pub(crate) fn strict_schema(parameters: &Parameters) -> Result<Value, OpenAIError> {
+    let mut schema = Property {
+        param_type: Some(parameters.param_type.clone()),
+        properties: Some(parameters.properties.clone()),
+        required: Some(parameters.required.clone()),
+        ..Default::default()
+    };
+    make_strict(&mut schema, "$")?;
+    Ok(serde_json::to_value(schema).unwrap_or_default())
+}
+
+fn make_strict(schema: &mut Property, path: &str) -> Result<(), OpenAIError> {
+    if schema.param_type.is_none() && schema.any_of.is_none() && schema.enum_values.is_none() {
+        return Err(OpenAIError::Validation(format!("Invalid schema: {} accepts any value. Strict schemas should declare a type.", path)));
+    }
+
+    if let Some(items) = &mut schema.items {
+        make_strict(items, &format!("{}[]", path))?;
+    }
+    for variant in schema.any_of.iter_mut().flatten() {
+        make_strict(variant, path)?;
+    }
+
+    let Some(properties) = &mut schema.properties else {
+        if schema.param_type.as_deref() == Some("object") {
+            return Err(OpenAIError::Validation(format!("Invalid schema: {} is a free-form object. Strict schemas should declare its properties.", path)));
+        }
+        return Ok(());
+    };
+    let mut names = Vec::new();
+    for (name, property) in properties.iter_mut() {
+        make_strict(property, &format!("{}.{}", path, name))?;
+        if property.nullable {
+            *property = Property { any_of: Some(vec![std::mem::take(property), typed("null")]), ..Default::default() };
+        }
+        names.push(name.clone());
+    }
+
+    schema.required = Some(names);
+    schema.additional_properties = Some(false);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parameters(properties: &[(&str, Property)], required: &[&str]) -> Parameters {
+        Parameters {
+            param_type: "object".to_string(),
+            properties: properties.iter().map(|(name, property)| (name.to_string(), property.clone())).collect(),
+            required: required.iter().map(|name| name.to_string()).collect(),
+            additional_properties: None,
+        }
+    }
+
+    #[test]
+    fn makes_optional_properties_nullable() {
+        let schema = strict_schema(&parameters(&[("name", String::property()), ("age", Option::<u32>::property())], &["name"])).unwrap();
+
+        assert_eq!(schema["properties"]["name"], json!({ "type": "string" }));
+        assert_eq!(schema["properties"]["age"], json!({ "anyOf": [{ "type": "integer" }, { "type": "null" }] }));
+        assert_eq!(schema["additionalProperties"], json!(false));
+        assert_eq!(schema["required"].as_array().map(Vec::len), Some(2));
+    }
+
+    #[test]
+    fn keeps_defaulted_properties_non_nullable() {
+        let schema = strict_schema(&parameters(&[("count", u32::property()), ("tags", Vec::<String>::property())], &[])).unwrap();
+
+        assert_eq!(schema["properties"]["count"], json!({ "type": "integer" }));
+        assert_eq!(schema["properties"]["tags"], json!({ "type": "array", "items": { "type": "string" } }));
+        assert_eq!(schema["required"].as_array().map(Vec::len), Some(2));
+    }
+
+    #[test]
+    fn rejects_free_form_objects() {
+        let error = strict_schema(&parameters(&[("tags", HashMap::<String, String>::property())], &["tags"])).unwrap_err();
+        assert!(matches!(&error, OpenAIError::Validation(message) if message.contains("$.tags")), "{}", error);
+
+        let error = strict_schema(&parameters(&[("rows", Vec::<BTreeMap<String, u32>>::property())], &["rows"])).unwrap_err();
+        assert!(matches!(&error, OpenAIError::Validation(message) if message.contains("$.rows[]")), "{}", error);
+    }
+
+    #[test]
+    fn rejects_empty_schemas() {
+        let error = strict_schema(&parameters(&[("extra", Option::<Value>::property())], &[])).unwrap_err();
+        assert!(matches!(&error, OpenAIError::Validation(message) if message.contains("$.extra")), "{}", error);
+    }
+}
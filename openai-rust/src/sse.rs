@@ -0,0 +1,257 @@
+use crate::error::OpenAIError;
+use bytes::Bytes;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use reqwest::{Response, StatusCode};
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+    pub id: Option<String>,
+}
+
+impl SseEvent {
+    pub fn is_done(&self) -> bool {
+        self.data == "[DONE]"
+    }
+}
+
+// Incremental decoder for the text/event-stream format. Bytes are buffered until a full line
+// is available, so events and multi-byte characters may be split across network chunks.
+#[derive(Debug, Default)]
+pub(crate) struct SseDecoder {
+    buffer: Vec<u8>,
+    skip_line_feed: bool,
+    event: Option<String>,
+    data: Vec<String>,
+    id: Option<String>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        let mut start = 0;
+
+        if self.skip_line_feed && self.buffer.first() == Some(&b'\n') {
+            start = 1;
+        }
+        self.skip_line_feed = false;
+
+        while let Some(offset) = self.buffer[start..].iter().position(|&b| b == b'\n' || b == b'\r') {
+            let end = start + offset;
+            let line = String::from_utf8_lossy(&self.buffer[start..end]).into_owned();
+            start = end + 1;
+
+            if self.buffer[end] == b'\r' {
+                match self.buffer.get(start) {
+                    Some(b'\n') => start += 1,
+                    Some(_) => {}
+                    None => self.skip_line_feed = true,
+                }
+            }
+
+            if let Some(event) = self.process_line(&line) {
+                events.push(event);
+            }
+        }
+
+        self.buffer.drain(..start);
+        events
+    }
+
+    /// Discards an event that was not followed by a blank line when the stream ends, as the spec
+    /// requires. Returns whether data was discarded, meaning the stream was cut off mid-event.
+    pub fn finish(&mut self) -> bool {
+        let truncated = !self.data.is_empty() || (!self.buffer.is_empty() && !self.buffer.starts_with(b":"));
+        *self = Self::default();
+        truncated
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+
+        Some(SseEvent {
+            event,
+            data: std::mem::take(&mut self.data).join("\n"),
+            id: self.id.clone(),
+        })
+    }
+}
+
+struct EventStreamState {
+    bytes: BoxStream<'static, reqwest::Result<Bytes>>,
+    decoder: SseDecoder,
+    pending: VecDeque<SseEvent>,
+    status: StatusCode,
+    eof: bool,
+    done: bool,
+}
+
+// Turns an event-stream response into a stream of JSON payloads. The stream ends at `[DONE]`,
+// after the first error, or when the body is exhausted; dropping it closes the connection.
+pub(crate) fn json_event_stream<T>(response: Response) -> impl Stream<Item = Result<T, OpenAIError>> + Send + Unpin
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let state = EventStreamState {
+        status: response.status(),
+        bytes: response.bytes_stream().boxed(),
+        decoder: SseDecoder::new(),
+        pending: VecDeque::new(),
+        eof: false,
+        done: false,
+    };
+
+    Box::pin(stream::unfold(state, |mut state| async move {
+        loop {
+            if state.done {
+                return None;
+            }
+
+            if let Some(event) = state.pending.pop_front() {
+                if event.is_done() {
+                    return None;
+                }
+                let item = parse_event(state.status, event);
+                state.done = item.is_err();
+                return Some((item, state));
+            }
+
+            if state.eof {
+                return None;
+            }
+
+            match state.bytes.next().await {
+                Some(Ok(chunk)) => state.pending.extend(state.decoder.feed(&chunk)),
+                Some(Err(e)) => {
+                    state.done = true;
+                    return Some((Err(e.into()), state));
+                }
+                None => {
+                    state.eof = true;
+                    if state.decoder.finish() {
+                        state.done = true;
+                        return Some((Err(OpenAIError::TruncatedStream), state));
+                    }
+                }
+            }
+        }
+    }))
+}
+
+fn parse_event<T: DeserializeOwned>(status: StatusCode, event: SseEvent) -> Result<T, OpenAIError> {
+    if event.event.as_deref() == Some("error") || event.data.contains("\"error\"") {
+        if let Some(error) = OpenAIError::from_error_body(status, &event.data) {
+            return Err(error);
+        }
+    }
+
+    serde_json::from_str(&event.data).map_err(|source| OpenAIError::Deserialize { source, body: event.data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_in_chunks(input: &[u8], chunk_size: usize) -> Vec<SseEvent> {
+        let mut decoder = SseDecoder::new();
+        let events: Vec<SseEvent> = input.chunks(chunk_size).flat_map(|chunk| decoder.feed(chunk)).collect();
+        assert!(!decoder.finish(), "chunk size {}", chunk_size);
+        events
+    }
+
+    #[test]
+    fn decodes_events_split_across_chunks() {
+        let input = "data: {\"id\":\"1\",\"text\":\"héllo\"}\n\ndata: {\"id\":\"2\"}\n\ndata: [DONE]\n\n".as_bytes();
+
+        for chunk_size in 1..input.len() {
+            let events = decode_in_chunks(input, chunk_size);
+            assert_eq!(events.len(), 3, "chunk size {}", chunk_size);
+            assert_eq!(events[0].data, "{\"id\":\"1\",\"text\":\"héllo\"}");
+            assert_eq!(events[1].data, "{\"id\":\"2\"}");
+            assert!(events[2].is_done());
+        }
+    }
+
+    #[test]
+    fn handles_crlf_line_endings() {
+        let input = b"event: message\r\nid: 7\r\ndata: first\r\n\r\ndata: second\r\r";
+
+        for chunk_size in 1..input.len() {
+            let events = decode_in_chunks(input, chunk_size);
+            assert_eq!(events.len(), 2, "chunk size {}", chunk_size);
+            assert_eq!(events[0], SseEvent { event: Some("message".to_string()), data: "first".to_string(), id: Some("7".to_string()) });
+            assert_eq!(events[1].data, "second");
+            assert_eq!(events[1].event, None);
+            assert_eq!(events[1].id, Some("7".to_string()));
+        }
+    }
+
+    #[test]
+    fn joins_multi_line_data_and_skips_comments() {
+        let input = b": keep-alive\n\ndata: line one\ndata:line two\ndata\n\n";
+        let events = decode_in_chunks(input, 5);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "line one\nline two\n");
+    }
+
+    #[test]
+    fn discards_unterminated_event_on_finish() {
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.feed(b"data: partial").is_empty());
+        assert!(decoder.finish());
+        assert!(!decoder.finish());
+
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.feed(b"data: complete line\n").is_empty());
+        assert!(decoder.finish());
+        assert!(decoder.feed(b"data: next\n\n").iter().all(|event| event.data == "next"));
+
+        let mut decoder = SseDecoder::new();
+        assert_eq!(decoder.feed(b"data: done\n\n: keep-alive").len(), 1);
+        assert!(!decoder.finish());
+    }
+
+    #[test]
+    fn parses_in_band_error_events() {
+        let event = SseEvent { data: "{\"error\":{\"message\":\"overloaded\",\"type\":\"server_error\",\"param\":null,\"code\":null}}".to_string(), ..Default::default() };
+
+        match parse_event::<serde_json::Value>(StatusCode::OK, event) {
+            Err(OpenAIError::Api { error, .. }) => assert_eq!(error.message, "overloaded"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use crate::client::OpenAIClient;
+use crate::error::OpenAIError;
+use crate::schema::FunctionParameters;
+use crate::types::{ChatCompletionRequest, ChatCompletionResponse, Function, FunctionCall, MessageContent, MessageRequest, Role, Tool, ToolCall};
+use futures::future::{self, BoxFuture, FutureExt};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+type ToolHandler = Arc<dyn Fn(String) -> BoxFuture<'static, Result<String, String>> + Send + Sync>;
+
+#[derive(Clone)]
+struct RegisteredTool {
+    function: Function,
+    handler: ToolHandler,
+}
+
+#[derive(Clone)]
+pub struct ToolRegistry {
+    tools: HashMap<String, RegisteredTool>,
+    // Kept apart from `tools` so that a timeout can be set before its tool is registered.
+    timeouts: HashMap<String, Duration>,
+    max_iterations: usize,
+    default_timeout: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolCallRecord {
+    pub iteration: usize,
+    pub id: Option<String>,
+    pub name: String,
+    pub arguments: String,
+    pub output: String,
+    pub is_error: bool,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolRun {
+    pub response: ChatCompletionResponse,
+    pub messages: Vec<MessageRequest>,
+    pub calls: Vec<ToolCallRecord>,
+}
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self {
+            tools: HashMap::new(),
+            timeouts: HashMap::new(),
+            max_iterations: 10,
+            default_timeout: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<F, Fut, E>(mut self, function: Function, handler: F) -> Self
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, E>> + Send + 'static,
+        E: Display,
+    {
+        let handler: ToolHandler = Arc::new(move |arguments| {
+            handler(arguments).map(|result| result.map_err(|e| e.to_string())).boxed()
+        });
+        self.tools.insert(function.name.clone(), RegisteredTool { function, handler });
+        self
+    }
+
+    /// Registers a handler whose arguments are parsed into `T`, with the schema generated from `T`.
+    pub fn register_typed<T, F, Fut, E>(self, name: impl Into<String>, description: impl Into<String>, handler: F) -> Self
+    where
+        T: FunctionParameters + Send + 'static,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, E>> + Send + 'static,
+        E: Display,
+    {
+        let handler = Arc::new(handler);
+        self.register(Function::from_parameters::<T>(name, description), move |arguments: String| {
+            let handler = handler.clone();
+            async move {
+                let arguments = serde_json::from_str::<T>(&arguments).map_err(|e| format!("Invalid arguments: {}", e))?;
+                handler(arguments).await.map_err(|e| e.to_string())
+            }
+        })
+    }
+
+    pub fn timeout(mut self, name: impl Into<String>, timeout: Duration) -> Self {
+        self.timeouts.insert(name.into(), timeout);
+        self
+    }
+
+    pub fn default_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.default_timeout = timeout;
+        self
+    }
+
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Sorted by name so that requests are identical between runs.
+    pub fn functions(&self) -> Vec<Function> {
+        let mut functions: Vec<Function> = self.tools.values().map(|tool| tool.function.clone()).collect();
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+        functions
+    }
+
+    pub fn tools(&self) -> Vec<Tool> {
+        self.functions().into_iter().map(Tool::from).collect()
+    }
+
+    /// Runs a single call. Unknown tools, handler errors and timeouts are reported as `Err` with a
+    /// message suitable for sending back to the model.
+    pub async fn call(&self, function_call: &FunctionCall) -> Result<String, String> {
+        let tool = self.tools.get(&function_call.name)
+            .ok_or_else(|| format!("Unknown function: {}", function_call.name))?;
+        let call = (tool.handler)(function_call.arguments.clone());
+
+        match self.timeouts.get(&function_call.name).copied().or(self.default_timeout) {
+            Some(timeout) => tokio::time::timeout(timeout, call).await
+                .unwrap_or_else(|_| Err(format!("Function {} timed out after {:?}", function_call.name, timeout))),
+            None => call.await,
+        }
+    }
+
+    pub(crate) async fn run(&self, client: &OpenAIClient, mut request: ChatCompletionRequest) -> Result<ToolRun, OpenAIError> {
+        if request.tools.is_none() && request.functions.is_none() && !self.tools.is_empty() {
+            request.tools = Some(self.tools());
+        }
+
+        let mut calls = Vec::new();
+        let mut response = ChatCompletionResponse::default();
+        for iteration in 1..=self.max_iterations {
+            response = client.chat(request.clone()).await?;
+            let Some(choice) = response.choices.first() else {
+                return Ok(ToolRun { response, messages: request.messages, calls });
+            };
+            let message = &choice.message;
+
+            let pending: Vec<(Option<String>, FunctionCall)> = match (&message.tool_calls, &message.function_call) {
+                (Some(tool_calls), _) if !tool_calls.is_empty() => tool_calls.iter().map(|call| (Some(call.id.clone()), call.function.clone())).collect(),
+                (_, Some(function_call)) => vec![(None, function_call.clone())],
+                _ => return Ok(ToolRun { response, messages: request.messages, calls }),
+            };
+
+            request.messages.push(MessageRequest {
+                role: Role::Assistant,
+                content: message.content.clone().map(MessageContent::from),
+                function_call: message.function_call.clone(),
+                tool_calls: message.tool_calls.clone().filter(|tool_calls: &Vec<ToolCall>| !tool_calls.is_empty()),
+                ..Default::default()
+            });
+
+            let results = future::join_all(pending.into_iter().map(|(id, function_call)| async move {
+                let started = Instant::now();
+                let result = self.call(&function_call).await;
+                (id, function_call, result, started.elapsed())
+            })).await;
+
+            for (id, function_call, result, duration) in results {
+                let is_error = result.is_err();
+                let output = result.unwrap_or_else(|e| e);
+
+                request.messages.push(match &id {
+                    Some(id) => MessageRequest { role: Role::Tool, content: Some(MessageContent::from(&output)), tool_call_id: Some(id.clone()), ..Default::default() },
+                    None => MessageRequest { role: Role::Function, content: Some(MessageContent::from(&output)), name: Some(function_call.name.clone()), ..Default::default() },
+                });
+                calls.push(ToolCallRecord { iteration, id, name: function_call.name, arguments: function_call.arguments, output, is_error, duration });
+            }
+        }
+
+        // The partial run ends with the results of the last calls, ready to be continued.
+        let run = ToolRun { response, messages: request.messages, calls };
+        Err(OpenAIError::ToolIterationLimit { limit: self.max_iterations, run: Box::new(run) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn function(name: &str) -> Function {
+        Function { name: name.to_string(), ..Default::default() }
+    }
+
+    async fn call(registry: &ToolRegistry) -> Result<String, String> {
+        registry.call(&FunctionCall { name: "sleep".to_string(), arguments: "{}".to_string() }).await
+    }
+
+    #[tokio::test]
+    async fn applies_timeouts_set_before_or_after_registering() {
+        let handler = |_: String| async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Ok::<_, String>("done".to_string())
+        };
+
+        let before = ToolRegistry::new().timeout("sleep", Duration::from_millis(10)).register(function("sleep"), handler);
+        let after = ToolRegistry::new().register(function("sleep"), handler).timeout("sleep", Duration::from_millis(10));
+        let other = ToolRegistry::new().register(function("sleep"), handler).timeout("slepe", Duration::from_millis(10));
+
+        assert!(call(&before).await.unwrap_err().contains("timed out"));
+        assert!(call(&after).await.unwrap_err().contains("timed out"));
+        assert_eq!(call(&other).await, Ok("done".to_string()));
+    }
+
+    #[test]
+    fn lists_tools_in_name_order() {
+        let handler = |_: String| async { Ok::<_, String>(String::new()) };
+        let registry = ToolRegistry::new()
+            .register(function("search"), handler)
+            .register(function("add"), handler)
+            .register(function("lookup"), handler);
+
+        let names: Vec<String> = registry.tools().into_iter().map(|tool| tool.function.name).collect();
+        assert_eq!(names, vec!["add", "lookup", "search"]);
+    }
+}
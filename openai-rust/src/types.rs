@@ -1,6 +1,11 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::DeserializeOwned};
+use serde_json::Value;
 use derive_builder::Builder;
 use std::collections::HashMap;
+use std::path::Path;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use crate::error::OpenAIError;
+use crate::schema::{strict_schema, FunctionParameters};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -10,10 +15,11 @@ pub enum Role {
     System,
     Assistant,
     Function,
+    Tool,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Builder)]
-#[builder(setter(into, strip_option), default, build_fn(validate = "Self::validate"))]
+#[builder(setter(into, strip_option), default, build_fn(validate = "Self::validate", error = "OpenAIError"))]
 pub struct ChatCompletionRequest {
     #[builder(default = "String::from(\"gpt-3.5-turbo\")")]
     pub model: String,
@@ -21,7 +27,15 @@ pub struct ChatCompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub functions: Option<Vec<Function>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub function_call: Option<String>,
+    pub function_call: Option<FunctionCallChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
     pub temperature: Option<f64>,
     pub top_p: Option<f64>,
     pub n: Option<i32>,
@@ -38,32 +52,305 @@ pub struct ChatCompletionRequest {
     pub user: Option<String>,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Builder)]
-#[builder(setter(into))]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Builder)]
+#[builder(setter(into), build_fn(error = "OpenAIError"))]
 pub struct Function {
     pub name: String,
+    #[serde(default)]
     pub description: String,
+    #[serde(default)]
     pub parameters: Parameters,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Builder)]
-#[builder(setter(into, strip_option), default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+#[builder(setter(into), build_fn(error = "OpenAIError"))]
+pub struct Tool {
+    #[serde(rename = "type")]
+    #[builder(default = "String::from(\"function\")")]
+    pub tool_type: String,
+    pub function: Function,
+}
+
+impl Function {
+    pub fn from_parameters<T: FunctionParameters>(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self { name: name.into(), description: description.into(), parameters: T::parameters() }
+    }
+}
+
+impl From<Function> for Tool {
+    fn from(function: Function) -> Self {
+        Self { tool_type: String::from("function"), function }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FunctionCallChoice {
+    Auto,
+    None,
+    Function(String),
+}
+
+impl Serialize for FunctionCallChoice {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            FunctionCallChoice::Auto => serializer.serialize_str("auto"),
+            FunctionCallChoice::None => serializer.serialize_str("none"),
+            FunctionCallChoice::Function(name) => serde_json::json!({ "name": name }).serialize(serializer),
+        }
+    }
+}
+
+impl From<&str> for FunctionCallChoice {
+    fn from(value: &str) -> Self {
+        match value {
+            "auto" => FunctionCallChoice::Auto,
+            "none" => FunctionCallChoice::None,
+            name => FunctionCallChoice::Function(name.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolChoice {
+    Auto,
+    None,
+    Required,
+    Function(String),
+}
+
+impl Serialize for ToolChoice {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ToolChoice::Auto => serializer.serialize_str("auto"),
+            ToolChoice::None => serializer.serialize_str("none"),
+            ToolChoice::Required => serializer.serialize_str("required"),
+            ToolChoice::Function(name) => serde_json::json!({ "type": "function", "function": { "name": name } }).serialize(serializer),
+        }
+    }
+}
+
+impl From<&str> for ToolChoice {
+    fn from(value: &str) -> Self {
+        match value {
+            "auto" => ToolChoice::Auto,
+            "none" => ToolChoice::None,
+            "required" => ToolChoice::Required,
+            name => ToolChoice::Function(name.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    Text,
+    JsonObject,
+    JsonSchema { json_schema: JsonSchema },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Builder)]
+#[builder(setter(into, strip_option), build_fn(error = "OpenAIError"))]
+pub struct JsonSchema {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub description: Option<String>,
+    pub schema: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub strict: Option<bool>,
+}
+
+impl ResponseFormat {
+    /// Strict structured output for `T`: every property is required, `Option` ones become nullable.
+    /// Fails when `T` contains maps or `serde_json::Value`, which strict schemas cannot describe.
+    pub fn json_schema<T: FunctionParameters>() -> Result<Self, OpenAIError> {
+        let name = std::any::type_name::<T>().rsplit("::").next().unwrap_or("response")
+            .chars().filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-').collect();
+        Ok(ResponseFormat::JsonSchema {
+            json_schema: JsonSchema { name, description: None, schema: strict_schema(&T::parameters())?, strict: Some(true) },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Builder)]
+#[builder(setter(into, strip_option), default, build_fn(error = "OpenAIError"))]
 pub struct MessageRequest {
     pub role: Role,
-    pub content: Option<String>,
+    pub content: Option<MessageContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub function_call: Option<FunctionCall>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl From<&str> for MessageContent {
+    fn from(text: &str) -> Self {
+        MessageContent::Text(text.to_string())
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(text: String) -> Self {
+        MessageContent::Text(text)
+    }
+}
+
+impl From<&String> for MessageContent {
+    fn from(text: &String) -> Self {
+        MessageContent::Text(text.clone())
+    }
+}
+
+impl From<Vec<ContentPart>> for MessageContent {
+    fn from(parts: Vec<ContentPart>) -> Self {
+        MessageContent::Parts(parts)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+    InputAudio { input_audio: InputAudio },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageUrl {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<ImageDetail>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageDetail {
+    Auto,
+    Low,
+    High,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputAudio {
+    pub data: String,
+    pub format: InputAudioFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputAudioFormat {
+    Wav,
+    Mp3,
+}
+
+const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+const IMAGE_MIME_TYPES: [&str; 4] = ["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+impl ContentPart {
+    pub fn text(text: impl Into<String>) -> Self {
+        ContentPart::Text { text: text.into() }
+    }
+
+    pub fn image_url(url: impl Into<String>, detail: Option<ImageDetail>) -> Self {
+        ContentPart::ImageUrl { image_url: ImageUrl { url: url.into(), detail } }
+    }
+
+    /// Encodes the image as a base64 data URL. The format is detected from the file signature.
+    pub fn image_bytes(bytes: &[u8], detail: Option<ImageDetail>) -> Result<Self, OpenAIError> {
+        if bytes.len() > MAX_IMAGE_BYTES {
+            return Err(OpenAIError::Validation(format!("Invalid image: {} bytes. It should be at most {} bytes.", bytes.len(), MAX_IMAGE_BYTES)));
+        }
+        let mime_type = image_mime_type(bytes)
+            .ok_or_else(|| OpenAIError::Validation("Invalid image: unsupported format. It should be PNG, JPEG, GIF or WEBP.".to_string()))?;
+
+        Ok(Self::image_url(format!("data:{};base64,{}", mime_type, BASE64_STANDARD.encode(bytes)), detail))
+    }
+
+    pub async fn image_file(path: impl AsRef<Path>, detail: Option<ImageDetail>) -> Result<Self, OpenAIError> {
+        Self::image_bytes(&tokio::fs::read(path).await?, detail)
+    }
+
+    pub fn input_audio(bytes: &[u8], format: InputAudioFormat) -> Self {
+        ContentPart::InputAudio { input_audio: InputAudio { data: BASE64_STANDARD.encode(bytes), format } }
+    }
+
+    pub async fn input_audio_file(path: impl AsRef<Path>) -> Result<Self, OpenAIError> {
+        let path = path.as_ref();
+        let format = match path.extension().and_then(|extension| extension.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("wav") => InputAudioFormat::Wav,
+            Some("mp3") => InputAudioFormat::Mp3,
+            _ => return Err(OpenAIError::Validation(format!("Invalid audio file: {}. It should be a .wav or .mp3 file.", path.display()))),
+        };
+        Ok(Self::input_audio(&tokio::fs::read(path).await?, format))
+    }
+
+    fn validate(&self) -> Result<(), OpenAIError> {
+        let ContentPart::ImageUrl { image_url } = self else { return Ok(()) };
+        let Some(data) = image_url.url.strip_prefix("data:") else { return Ok(()) };
+
+        let (mime_type, payload) = data.split_once(";base64,")
+            .ok_or_else(|| OpenAIError::Validation("Invalid image data URL. It should be base64 encoded.".to_string()))?;
+        if !IMAGE_MIME_TYPES.contains(&mime_type) {
+            return Err(OpenAIError::Validation(format!("Invalid image format: {}. It should be one of {}.", mime_type, IMAGE_MIME_TYPES.join(", "))));
+        }
+        if payload.len() / 4 * 3 > MAX_IMAGE_BYTES {
+            return Err(OpenAIError::Validation(format!("Invalid image: more than {} bytes.", MAX_IMAGE_BYTES)));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn image_mime_type(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        _ => None,
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, Builder)]
-#[builder(setter(into))]
+#[builder(setter(into), build_fn(error = "OpenAIError"))]
 pub struct FunctionCall {
     pub name: String,
     pub arguments: String,
 }
 
+impl FunctionCall {
+    pub fn parse_arguments<T: DeserializeOwned>(&self) -> Result<T, OpenAIError> {
+        serde_json::from_str(&self.arguments).map_err(|source| OpenAIError::Deserialize { source, body: self.arguments.clone() })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+#[builder(setter(into), build_fn(error = "OpenAIError"))]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type")]
+    #[builder(default = "String::from(\"function\")")]
+    pub tool_type: String,
+    pub function: FunctionCall,
+}
+
+impl Default for ToolCall {
+    fn default() -> Self {
+        Self { id: String::new(), tool_type: String::from("function"), function: FunctionCall::default() }
+    }
+}
+
 #[derive(Debug, Clone, Default, Deserialize)]
 pub struct ChatCompletionResponse {
     pub id: String,
@@ -86,30 +373,74 @@ pub struct MessageResponse {
     pub role: Role,
     pub content: Option<String>,
     pub function_call: Option<FunctionCall>,
+    pub tool_calls: Option<Vec<ToolCall>>,
+    pub refusal: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
 pub struct Usage {
     pub prompt_tokens: i32,
+    #[serde(default)]
     pub completion_tokens: i32,
     pub total_tokens: i32,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Builder)]
-#[builder(setter(into))]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Builder)]
+#[builder(setter(into), build_fn(error = "OpenAIError"))]
 pub struct Parameters {
     #[serde(rename = "type")]
+    #[builder(default = "String::from(\"object\")")]
     pub param_type: String,
+    #[serde(default)]
     pub properties: HashMap<String, Property>,
+    #[serde(default)]
+    #[builder(default)]
     pub required: Vec<String>,
+    #[serde(rename = "additionalProperties", skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    pub additional_properties: Option<bool>,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Builder)]
-#[builder(setter(into))]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Builder)]
+#[builder(setter(into, strip_option), default, build_fn(error = "OpenAIError"))]
 pub struct Property {
-    #[serde(rename = "type")]
-    pub param_type: String,
-    pub description: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub param_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
+    pub enum_values: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<Box<Property>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub properties: Option<HashMap<String, Property>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<Vec<String>>,
+    #[serde(rename = "additionalProperties", skip_serializing_if = "Option::is_none")]
+    pub additional_properties: Option<bool>,
+    #[serde(rename = "anyOf", skip_serializing_if = "Option::is_none")]
+    pub any_of: Option<Vec<Property>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<f64>,
+    #[serde(rename = "minLength", skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<u64>,
+    #[serde(rename = "maxLength", skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<u64>,
+    #[serde(rename = "minItems", skip_serializing_if = "Option::is_none")]
+    pub min_items: Option<u64>,
+    #[serde(rename = "maxItems", skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(rename = "default", skip_serializing_if = "Option::is_none")]
+    pub default_value: Option<Value>,
+    /// Marks `Option` values, which strict schemas make nullable. It is not part of the JSON schema.
+    #[serde(skip)]
+    pub nullable: bool,
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -119,6 +450,8 @@ pub struct StreamResponse {
     pub created: u32,
     pub model: String,
     pub choices: Vec<Choice>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -132,9 +465,27 @@ pub struct Choice {
 pub struct Delta {
     pub role: Option<String>,
     pub content: Option<String>,
+    pub function_call: Option<FunctionCallDelta>,
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FunctionCallDelta {
+    pub name: Option<String>,
+    pub arguments: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ToolCallDelta {
+    pub index: u32,
+    pub id: Option<String>,
+    #[serde(rename = "type")]
+    pub tool_type: Option<String>,
+    pub function: Option<FunctionCallDelta>,
 }
+
 #[derive(Debug, Clone, Default, Builder)]
-#[builder(setter(into, strip_option), default, build_fn(validate = "Self::validate"))]
+#[builder(setter(into, strip_option), default, build_fn(validate = "Self::validate", error = "OpenAIError"))]
 pub struct TranscriptionRequest {
     pub file: String,
     #[builder(default = "String::from(\"whisper-1\")")]
@@ -151,7 +502,7 @@ pub struct TranscriptionResponse {
 }
 
 #[derive(Debug, Clone, Default, Builder)]
-#[builder(setter(into, strip_option), default, build_fn(validate = "Self::validate"))]
+#[builder(setter(into, strip_option), default, build_fn(validate = "Self::validate", error = "OpenAIError"))]
 pub struct TranslationRequest {
     pub file: String,
     #[builder(default = "String::from(\"whisper-1\")")]
@@ -166,92 +517,1202 @@ pub struct TranslationResponse {
     pub text: String,
 }
 
+macro_rules! string_enum {
+    ($name:ident { $($variant:ident => $value:literal),+ $(,)? }) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+        pub enum $name {
+            $(#[serde(rename = $value)] $variant),+
+        }
+
+        impl $name {
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $value),+
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    };
+}
+
+string_enum!(ImageSize {
+    Square256 => "256x256",
+    Square512 => "512x512",
+    Square1024 => "1024x1024",
+    Landscape1792 => "1792x1024",
+    Portrait1792 => "1024x1792",
+    Landscape1536 => "1536x1024",
+    Portrait1536 => "1024x1536",
+    Auto => "auto",
+});
+
+string_enum!(ImageQuality {
+    Standard => "standard",
+    Hd => "hd",
+    Low => "low",
+    Medium => "medium",
+    High => "high",
+    Auto => "auto",
+});
+
+string_enum!(ImageStyle {
+    Vivid => "vivid",
+    Natural => "natural",
+});
+
+string_enum!(ImageBackground {
+    Transparent => "transparent",
+    Opaque => "opaque",
+    Auto => "auto",
+});
+
+string_enum!(ImageOutputFormat {
+    Png => "png",
+    Jpeg => "jpeg",
+    Webp => "webp",
+});
+
 #[derive(Debug, Clone, Default, Serialize, Builder)]
-#[builder(setter(into, strip_option), default, build_fn(validate = "Self::validate"))]
+#[builder(setter(into, strip_option), default, build_fn(validate = "Self::validate", error = "OpenAIError"))]
 pub struct ImageRequest {
     pub prompt: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub n: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub size: Option<String>,
+    pub size: Option<ImageSize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<ImageQuality>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<ImageStyle>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<ImageBackground>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_format: Option<ImageOutputFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_compression: Option<u8>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_format: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
 }
 
+#[derive(Debug, Clone, Default, Builder)]
+#[builder(setter(into, strip_option), default, build_fn(validate = "Self::validate", error = "OpenAIError"))]
+pub struct ImageEditRequest {
+    pub image: String,
+    pub mask: Option<String>,
+    pub prompt: String,
+    pub n: Option<i32>,
+    pub size: Option<ImageSize>,
+    pub response_format: Option<String>,
+    pub user: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Builder)]
+#[builder(setter(into, strip_option), default, build_fn(validate = "Self::validate", error = "OpenAIError"))]
+pub struct ImageVariationRequest {
+    pub image: String,
+    pub n: Option<i32>,
+    pub size: Option<ImageSize>,
+    pub response_format: Option<String>,
+    pub user: Option<String>,
+}
+
 #[derive(Debug, Clone, Default, Deserialize)]
 pub struct ImageResponse {
     pub created: u32,
     pub data: Vec<Image>,
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Image {
-    pub url: String,
+    #[serde(flatten)]
+    pub data: ImageData,
+    pub revised_prompt: Option<String>,
 }
 
-impl ChatCompletionRequestBuilder {
-    fn validate(&self) -> Result<(), String> {
-        if let Some(temp) = self.temperature {
-            let temperature_value = temp.unwrap();
-            if temperature_value < 0.0 || temperature_value > 2.0 {
-                return Err(format!("Invalid temperature: {}. It should be between 0.0 and 2.0.", temperature_value));
-            }
-        }
+/// Generated images are returned as a temporary URL or, with `response_format` set to
+/// `b64_json`, as base64 encoded bytes.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum ImageData {
+    Url { url: String },
+    Base64 { b64_json: String },
+}
 
-        if let Some(presence_penalty) = self.presence_penalty {
-            let presence_penalty_value = presence_penalty.unwrap();
-            if presence_penalty_value < -2.0 || presence_penalty_value > 2.0 {
-                return Err(format!("Invalid presence_penalty: {}. It should be between -2.0 and 2.0.", presence_penalty_value));
-            }
+impl Image {
+    pub fn url(&self) -> Option<&str> {
+        match &self.data {
+            ImageData::Url { url } => Some(url),
+            ImageData::Base64 { .. } => None,
         }
+    }
 
-        if let Some(frequency_penalty) = self.frequency_penalty {
-            let frequency_penalty_value = frequency_penalty.unwrap();
-            if frequency_penalty_value < 0.0 || frequency_penalty_value > 2.0 {
-                return Err(format!("Invalid frequency_penalty: {}. It should be between -2.0 and 2.0.", frequency_penalty_value));
-            }
+    pub fn b64_json(&self) -> Option<&str> {
+        match &self.data {
+            ImageData::Base64 { b64_json } => Some(b64_json),
+            ImageData::Url { .. } => None,
         }
-            
-        Ok(())
+    }
+
+    /// Decodes a base64 image. Returns `None` for images returned as URLs.
+    pub fn decode(&self) -> Option<Result<Vec<u8>, OpenAIError>> {
+        self.b64_json().map(decode_image)
     }
 }
 
-impl TranscriptionRequestBuilder {
-    fn validate(&self) -> Result<(), String> {
-        if let Some(temp) = self.temperature {
-            let temperature_value = temp.unwrap();
-            if temperature_value < 0.0 || temperature_value > 1.0 {
-                return Err(format!("Invalid temperature: {}. It should be between 0.0 and 1.0.", temperature_value));
-            }
-        }
-            
-        Ok(())
+pub(crate) fn decode_image(b64_json: &str) -> Result<Vec<u8>, OpenAIError> {
+    BASE64_STANDARD.decode(b64_json).map_err(|e| OpenAIError::Validation(format!("Invalid b64_json image: {}", e)))
+}
+
+string_enum!(Voice {
+    Alloy => "alloy",
+    Ash => "ash",
+    Ballad => "ballad",
+    Coral => "coral",
+    Echo => "echo",
+    Fable => "fable",
+    Onyx => "onyx",
+    Nova => "nova",
+    Sage => "sage",
+    Shimmer => "shimmer",
+    Verse => "verse",
+});
+
+string_enum!(SpeechFormat {
+    Mp3 => "mp3",
+    Opus => "opus",
+    Aac => "aac",
+    Flac => "flac",
+    Wav => "wav",
+    Pcm => "pcm",
+});
+
+#[derive(Debug, Clone, Serialize, Builder)]
+#[builder(setter(into, strip_option), build_fn(validate = "Self::validate", error = "OpenAIError"))]
+pub struct SpeechRequest {
+    #[builder(default = "String::from(\"gpt-4o-mini-tts\")")]
+    pub model: String,
+    pub input: String,
+    pub voice: Voice,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub response_format: Option<SpeechFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub speed: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub instructions: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum EmbeddingInput {
+    Text(String),
+    TextArray(Vec<String>),
+    Tokens(Vec<u32>),
+    TokenArrays(Vec<Vec<u32>>),
+}
+
+impl Default for EmbeddingInput {
+    fn default() -> Self {
+        EmbeddingInput::TextArray(Vec::new())
     }
 }
 
-impl TranslationRequestBuilder {
-    fn validate(&self) -> Result<(), String> {
-        if let Some(temp) = self.temperature {
-            let temperature_value = temp.unwrap();
-            if temperature_value < 0.0 || temperature_value > 1.0 {
-                return Err(format!("Invalid temperature: {}. It should be between 0.0 and 1.0.", temperature_value));
-            }
-        }
-            
-        Ok(())
+impl From<&str> for EmbeddingInput {
+    fn from(text: &str) -> Self {
+        EmbeddingInput::Text(text.to_string())
     }
 }
 
-impl ImageRequestBuilder {
-    fn validate(&self) -> Result<(), String> {
-        if let Some(prompt) = &self.prompt {
-            if prompt.len() > 1000 {
-                return Err(format!("Invalid prompt: {}. It should be less than 1000 characters.", prompt.len()));
+impl From<String> for EmbeddingInput {
+    fn from(text: String) -> Self {
+        EmbeddingInput::Text(text)
+    }
+}
+
+impl From<Vec<String>> for EmbeddingInput {
+    fn from(texts: Vec<String>) -> Self {
+        EmbeddingInput::TextArray(texts)
+    }
+}
+
+impl From<Vec<&str>> for EmbeddingInput {
+    fn from(texts: Vec<&str>) -> Self {
+        EmbeddingInput::TextArray(texts.into_iter().map(String::from).collect())
+    }
+}
+
+impl From<Vec<u32>> for EmbeddingInput {
+    fn from(tokens: Vec<u32>) -> Self {
+        EmbeddingInput::Tokens(tokens)
+    }
+}
+
+impl From<Vec<Vec<u32>>> for EmbeddingInput {
+    fn from(tokens: Vec<Vec<u32>>) -> Self {
+        EmbeddingInput::TokenArrays(tokens)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EncodingFormat {
+    Float,
+    Base64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Builder)]
+#[builder(setter(into, strip_option), default, build_fn(validate = "Self::validate", error = "OpenAIError"))]
+pub struct EmbeddingRequest {
+    #[builder(default = "String::from(\"text-embedding-3-small\")")]
+    pub model: String,
+    pub input: EmbeddingInput,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding_format: Option<EncodingFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EmbeddingResponse {
+    pub object: String,
+    pub data: Vec<Embedding>,
+    pub model: String,
+    pub usage: Usage,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Embedding {
+    pub object: String,
+    pub index: u32,
+    #[serde(deserialize_with = "deserialize_embedding")]
+    pub embedding: Vec<f32>,
+}
+
+// Embeddings requested with `EncodingFormat::Base64` arrive as little-endian f32 bytes.
+fn deserialize_embedding<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f32>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawEmbedding {
+        Float(Vec<f32>),
+        Base64(String),
+    }
+
+    match RawEmbedding::deserialize(deserializer)? {
+        RawEmbedding::Float(values) => Ok(values),
+        RawEmbedding::Base64(encoded) => {
+            let bytes = BASE64_STANDARD.decode(encoded).map_err(serde::de::Error::custom)?;
+            if bytes.len() % 4 != 0 {
+                return Err(serde::de::Error::custom("base64 embedding length is not a multiple of 4 bytes"));
             }
+            Ok(bytes.chunks_exact(4).map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])).collect())
         }
-            
-        Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ModerationInput {
+    Text(String),
+    TextArray(Vec<String>),
+    Parts(Vec<ModerationPart>),
+}
+
+/// Text and image inputs of a single multimodal item. Images require an omni-moderation model.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ModerationPart {
+    Text { text: String },
+    ImageUrl { image_url: ModerationImageUrl },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModerationImageUrl {
+    pub url: String,
+}
+
+impl Default for ModerationInput {
+    fn default() -> Self {
+        ModerationInput::TextArray(Vec::new())
+    }
+}
+
+impl From<&str> for ModerationInput {
+    fn from(text: &str) -> Self {
+        ModerationInput::Text(text.to_string())
+    }
+}
+
+impl From<String> for ModerationInput {
+    fn from(text: String) -> Self {
+        ModerationInput::Text(text)
+    }
+}
+
+impl From<Vec<String>> for ModerationInput {
+    fn from(texts: Vec<String>) -> Self {
+        ModerationInput::TextArray(texts)
+    }
+}
+
+impl From<Vec<&str>> for ModerationInput {
+    fn from(texts: Vec<&str>) -> Self {
+        ModerationInput::TextArray(texts.into_iter().map(String::from).collect())
+    }
+}
+
+impl From<Vec<ModerationPart>> for ModerationInput {
+    fn from(parts: Vec<ModerationPart>) -> Self {
+        ModerationInput::Parts(parts)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Builder)]
+#[builder(setter(into, strip_option), default, build_fn(validate = "Self::validate", error = "OpenAIError"))]
+pub struct ModerationRequest {
+    pub input: ModerationInput,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ModerationResponse {
+    pub id: String,
+    pub model: String,
+    pub results: Vec<ModerationResult>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ModerationResult {
+    pub flagged: bool,
+    pub categories: ModerationCategories,
+    pub category_scores: ModerationCategoryScores,
+}
+
+macro_rules! moderation_categories {
+    ($($field:ident => $name:literal),+ $(,)?) => {
+        #[derive(Debug, Clone, Default, Deserialize)]
+        pub struct ModerationCategories {
+            $(
+                #[serde(rename = $name, default)]
+                pub $field: bool,
+            )+
+        }
+
+        #[derive(Debug, Clone, Default, Deserialize)]
+        pub struct ModerationCategoryScores {
+            $(
+                #[serde(rename = $name, default)]
+                pub $field: f64,
+            )+
+        }
+
+        impl ModerationCategories {
+            pub fn flagged(&self) -> Vec<&'static str> {
+                let mut flagged = Vec::new();
+                $(
+                    if self.$field {
+                        flagged.push($name);
+                    }
+                )+
+                flagged
+            }
+        }
+    };
+}
+
+moderation_categories! {
+    harassment => "harassment",
+    harassment_threatening => "harassment/threatening",
+    hate => "hate",
+    hate_threatening => "hate/threatening",
+    illicit => "illicit",
+    illicit_violent => "illicit/violent",
+    self_harm => "self-harm",
+    self_harm_intent => "self-harm/intent",
+    self_harm_instructions => "self-harm/instructions",
+    sexual => "sexual",
+    sexual_minors => "sexual/minors",
+    violence => "violence",
+    violence_graphic => "violence/graphic",
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Model {
+    pub id: String,
+    pub object: String,
+    pub created: u32,
+    pub owned_by: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ModelList {
+    pub object: String,
+    pub data: Vec<Model>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DeletedModel {
+    pub id: String,
+    pub object: String,
+    pub deleted: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilePurpose {
+    Assistants,
+    AssistantsOutput,
+    Batch,
+    BatchOutput,
+    #[serde(rename = "fine-tune")]
+    FineTune,
+    #[serde(rename = "fine-tune-results")]
+    FineTuneResults,
+    Vision,
+    UserData,
+    #[serde(other)]
+    Other,
+}
+
+impl FilePurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilePurpose::Assistants => "assistants",
+            FilePurpose::AssistantsOutput => "assistants_output",
+            FilePurpose::Batch => "batch",
+            FilePurpose::BatchOutput => "batch_output",
+            FilePurpose::FineTune => "fine-tune",
+            FilePurpose::FineTuneResults => "fine-tune-results",
+            FilePurpose::Vision => "vision",
+            FilePurpose::UserData => "user_data",
+            FilePurpose::Other => "other",
+        }
+    }
+}
+
+impl std::fmt::Display for FilePurpose {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileObject {
+    pub id: String,
+    pub object: String,
+    pub bytes: u64,
+    pub created_at: u64,
+    pub filename: String,
+    pub purpose: FilePurpose,
+    pub status: Option<String>,
+    pub status_details: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FileList {
+    pub object: String,
+    pub data: Vec<FileObject>,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DeletedFile {
+    pub id: String,
+    pub object: String,
+    pub deleted: bool,
+}
+
+/// Cursor for list endpoints: `after` is the id of the last item of the previous page.
+#[derive(Debug, Clone, Default)]
+pub struct Pagination {
+    pub after: Option<String>,
+    pub limit: Option<u32>,
+}
+
+impl Pagination {
+    pub fn after(after: impl Into<String>) -> Self {
+        Self { after: Some(after.into()), limit: None }
+    }
+
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub(crate) fn query(&self) -> String {
+        let params: Vec<String> = self.after.iter().map(|after| format!("after={}", after))
+            .chain(self.limit.iter().map(|limit| format!("limit={}", limit)))
+            .collect();
+        if params.is_empty() { String::new() } else { format!("?{}", params.join("&")) }
+    }
+}
+
+/// A hyperparameter left to the API with "auto" or set explicitly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hyperparameter<T> {
+    Auto,
+    Value(T),
+}
+
+impl<T> From<T> for Hyperparameter<T> {
+    fn from(value: T) -> Self {
+        Hyperparameter::Value(value)
+    }
+}
+
+impl<T: Serialize> Serialize for Hyperparameter<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Hyperparameter::Auto => serializer.serialize_str("auto"),
+            Hyperparameter::Value(value) => value.serialize(serializer),
+        }
+    }
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Hyperparameter<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::String(value) if value == "auto" => Ok(Hyperparameter::Auto),
+            value => serde_json::from_value(value).map(Hyperparameter::Value).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Builder)]
+#[builder(setter(into, strip_option), default, build_fn(error = "OpenAIError"))]
+pub struct Hyperparameters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<Hyperparameter<u32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub learning_rate_multiplier: Option<Hyperparameter<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n_epochs: Option<Hyperparameter<u32>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Builder)]
+#[builder(setter(into, strip_option), default, build_fn(validate = "Self::validate", error = "OpenAIError"))]
+pub struct FineTuningJobRequest {
+    pub model: String,
+    pub training_file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hyperparameters: Option<Hyperparameters>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FineTuningJobStatus {
+    ValidatingFiles,
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl FineTuningJobStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, FineTuningJobStatus::Succeeded | FineTuningJobStatus::Failed | FineTuningJobStatus::Cancelled)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FineTuningJobError {
+    pub code: Option<String>,
+    pub message: Option<String>,
+    pub param: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FineTuningJob {
+    pub id: String,
+    pub object: String,
+    pub model: String,
+    pub created_at: u64,
+    pub finished_at: Option<u64>,
+    pub fine_tuned_model: Option<String>,
+    pub organization_id: Option<String>,
+    pub status: FineTuningJobStatus,
+    #[serde(default)]
+    pub hyperparameters: Hyperparameters,
+    pub training_file: String,
+    pub validation_file: Option<String>,
+    #[serde(default)]
+    pub result_files: Vec<String>,
+    pub trained_tokens: Option<u64>,
+    pub error: Option<FineTuningJobError>,
+    pub seed: Option<u64>,
+    pub user_provided_suffix: Option<String>,
+    pub estimated_finish: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FineTuningJobList {
+    pub object: String,
+    pub data: Vec<FineTuningJob>,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FineTuningJobEvent {
+    pub id: String,
+    pub object: String,
+    pub created_at: u64,
+    pub level: String,
+    pub message: String,
+    #[serde(rename = "type")]
+    pub event_type: Option<String>,
+    pub data: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FineTuningJobEventList {
+    pub object: String,
+    pub data: Vec<FineTuningJobEvent>,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FineTuningCheckpointMetrics {
+    pub step: Option<f64>,
+    pub train_loss: Option<f64>,
+    pub train_mean_token_accuracy: Option<f64>,
+    pub valid_loss: Option<f64>,
+    pub valid_mean_token_accuracy: Option<f64>,
+    pub full_valid_loss: Option<f64>,
+    pub full_valid_mean_token_accuracy: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FineTuningJobCheckpoint {
+    pub id: String,
+    pub object: String,
+    pub created_at: u64,
+    pub fine_tuned_model_checkpoint: String,
+    pub fine_tuning_job_id: String,
+    pub step_number: u64,
+    #[serde(default)]
+    pub metrics: FineTuningCheckpointMetrics,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FineTuningJobCheckpointList {
+    pub object: String,
+    pub data: Vec<FineTuningJobCheckpoint>,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Builder)]
+#[builder(setter(into, strip_option), default, build_fn(validate = "Self::validate", error = "OpenAIError"))]
+pub struct BatchRequest {
+    pub input_file_id: String,
+    #[builder(default = "String::from(\"/v1/chat/completions\")")]
+    pub endpoint: String,
+    #[builder(default = "String::from(\"24h\")")]
+    pub completion_window: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Validating,
+    Failed,
+    InProgress,
+    Finalizing,
+    Completed,
+    Expired,
+    Cancelling,
+    Cancelled,
+}
+
+impl BatchStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, BatchStatus::Failed | BatchStatus::Completed | BatchStatus::Expired | BatchStatus::Cancelled)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BatchRequestCounts {
+    pub total: u32,
+    pub completed: u32,
+    pub failed: u32,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BatchError {
+    pub code: Option<String>,
+    pub message: Option<String>,
+    pub param: Option<String>,
+    pub line: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BatchErrors {
+    pub object: String,
+    pub data: Vec<BatchError>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Batch {
+    pub id: String,
+    pub object: String,
+    pub endpoint: String,
+    pub errors: Option<BatchErrors>,
+    pub input_file_id: String,
+    pub completion_window: String,
+    pub status: BatchStatus,
+    pub output_file_id: Option<String>,
+    pub error_file_id: Option<String>,
+    pub created_at: u64,
+    pub in_progress_at: Option<u64>,
+    pub expires_at: Option<u64>,
+    pub finalizing_at: Option<u64>,
+    pub completed_at: Option<u64>,
+    pub failed_at: Option<u64>,
+    pub expired_at: Option<u64>,
+    pub cancelling_at: Option<u64>,
+    pub cancelled_at: Option<u64>,
+    #[serde(default)]
+    pub request_counts: BatchRequestCounts,
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BatchList {
+    pub object: String,
+    pub data: Vec<Batch>,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+impl ChatCompletionRequest {
+    /// Text of every user message, as sent to the moderation endpoint by the client's guard mode.
+    pub fn user_text(&self) -> Vec<String> {
+        self.messages.iter()
+            .filter(|message| matches!(message.role, Role::User))
+            .flat_map(|message| match &message.content {
+                Some(MessageContent::Text(text)) => vec![text.clone()],
+                Some(MessageContent::Parts(parts)) => parts.iter().filter_map(|part| match part {
+                    ContentPart::Text { text } => Some(text.clone()),
+                    _ => None,
+                }).collect(),
+                None => Vec::new(),
+            })
+            .filter(|text| !text.is_empty())
+            .collect()
+    }
+
+    /// Text and image inputs of every user message. Audio inputs cannot be moderated and are left out.
+    pub fn user_moderation_input(&self) -> Vec<ModerationPart> {
+        self.messages.iter()
+            .filter(|message| matches!(message.role, Role::User))
+            .flat_map(|message| match &message.content {
+                Some(MessageContent::Text(text)) => vec![ModerationPart::Text { text: text.clone() }],
+                Some(MessageContent::Parts(parts)) => parts.iter().filter_map(|part| match part {
+                    ContentPart::Text { text } => Some(ModerationPart::Text { text: text.clone() }),
+                    ContentPart::ImageUrl { image_url } => Some(ModerationPart::ImageUrl { image_url: ModerationImageUrl { url: image_url.url.clone() } }),
+                    ContentPart::InputAudio { .. } => None,
+                }).collect(),
+                None => Vec::new(),
+            })
+            .filter(|part| !matches!(part, ModerationPart::Text { text } if text.is_empty()))
+            .collect()
+    }
+}
+
+impl ChatCompletionRequestBuilder {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        if let Some(Some(temperature)) = self.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                return Err(OpenAIError::Validation(format!("Invalid temperature: {}. It should be between 0.0 and 2.0.", temperature)));
+            }
+        }
+
+        if let Some(Some(presence_penalty)) = self.presence_penalty {
+            if !(-2.0..=2.0).contains(&presence_penalty) {
+                return Err(OpenAIError::Validation(format!("Invalid presence_penalty: {}. It should be between -2.0 and 2.0.", presence_penalty)));
+            }
+        }
+
+        if let Some(Some(frequency_penalty)) = self.frequency_penalty {
+            if !(-2.0..=2.0).contains(&frequency_penalty) {
+                return Err(OpenAIError::Validation(format!("Invalid frequency_penalty: {}. It should be between -2.0 and 2.0.", frequency_penalty)));
+            }
+        }
+
+        for message in self.messages.iter().flatten() {
+            if let Some(MessageContent::Parts(parts)) = &message.content {
+                parts.iter().try_for_each(ContentPart::validate)?;
+            }
+        }
+
+        if let Some(Some(FunctionCallChoice::Function(name))) = &self.function_call {
+            if !self.functions.iter().flatten().flatten().any(|function| &function.name == name) {
+                return Err(OpenAIError::Validation(format!("Invalid function_call: {}. No function with that name is defined in functions.", name)));
+            }
+        }
+
+        let mut tools = self.tools.iter().flatten().flatten();
+        match &self.tool_choice {
+            Some(Some(ToolChoice::Function(name))) if !tools.any(|tool| &tool.function.name == name) => {
+                return Err(OpenAIError::Validation(format!("Invalid tool_choice: {}. No tool with that name is defined in tools.", name)));
+            }
+            Some(Some(ToolChoice::Required)) if tools.next().is_none() => {
+                return Err(OpenAIError::Validation("Invalid tool_choice: required. At least one tool must be defined in tools.".to_string()));
+            }
+            _ => {}
+        }
+            
+        Ok(())
+    }
+}
+
+impl TranscriptionRequestBuilder {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        if let Some(Some(temperature)) = self.temperature {
+            if !(0.0..=1.0).contains(&temperature) {
+                return Err(OpenAIError::Validation(format!("Invalid temperature: {}. It should be between 0.0 and 1.0.", temperature)));
+            }
+        }
+            
+        Ok(())
+    }
+}
+
+impl TranslationRequestBuilder {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        if let Some(Some(temperature)) = self.temperature {
+            if !(0.0..=1.0).contains(&temperature) {
+                return Err(OpenAIError::Validation(format!("Invalid temperature: {}. It should be between 0.0 and 1.0.", temperature)));
+            }
+        }
+            
+        Ok(())
+    }
+}
+
+// What each image model accepts, matched by prefix. Requests without a model use dall-e-2.
+struct ImageModelLimits {
+    name: &'static str,
+    sizes: &'static [ImageSize],
+    max_n: i32,
+    max_prompt_chars: usize,
+    qualities: &'static [ImageQuality],
+    styles: bool,
+    gpt_image: bool,
+}
+
+const IMAGE_MODELS: [ImageModelLimits; 3] = [
+    ImageModelLimits {
+        name: "dall-e-2",
+        sizes: &[ImageSize::Square256, ImageSize::Square512, ImageSize::Square1024],
+        max_n: 10,
+        max_prompt_chars: 1000,
+        qualities: &[ImageQuality::Standard],
+        styles: false,
+        gpt_image: false,
+    },
+    ImageModelLimits {
+        name: "dall-e-3",
+        sizes: &[ImageSize::Square1024, ImageSize::Landscape1792, ImageSize::Portrait1792],
+        max_n: 1,
+        max_prompt_chars: 4000,
+        qualities: &[ImageQuality::Standard, ImageQuality::Hd],
+        styles: true,
+        gpt_image: false,
+    },
+    ImageModelLimits {
+        name: "gpt-image",
+        sizes: &[ImageSize::Square1024, ImageSize::Landscape1536, ImageSize::Portrait1536, ImageSize::Auto],
+        max_n: 10,
+        max_prompt_chars: 32000,
+        qualities: &[ImageQuality::Low, ImageQuality::Medium, ImageQuality::High, ImageQuality::Auto],
+        styles: false,
+        gpt_image: true,
+    },
+];
+
+fn image_model_limits(model: Option<&str>) -> Option<&'static ImageModelLimits> {
+    let model = model.unwrap_or("dall-e-2");
+    IMAGE_MODELS.iter().find(|limits| model.starts_with(limits.name))
+}
+
+fn join<T: std::fmt::Display>(values: &[T]) -> String {
+    values.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
+// Checks the options shared by generations, edits and variations against the limits of the
+// model. Unknown models only get the checks that do not depend on the model.
+fn validate_image_options(model: Option<&str>, prompt: Option<&str>, n: Option<i32>, size: Option<ImageSize>, quality: Option<ImageQuality>, response_format: Option<&str>) -> Result<(), OpenAIError> {
+    if let Some(response_format) = response_format {
+        if response_format != "url" && response_format != "b64_json" {
+            return Err(OpenAIError::Validation(format!("Invalid response_format: {}. It should be url or b64_json.", response_format)));
+        }
+    }
+
+    let Some(limits) = image_model_limits(model) else { return Ok(()) };
+    let name = model.unwrap_or(limits.name);
+
+    if let Some(prompt) = prompt {
+        let length = prompt.chars().count();
+        if length > limits.max_prompt_chars {
+            return Err(OpenAIError::Validation(format!("Invalid prompt: {} characters. It should be at most {} characters for {}.", length, limits.max_prompt_chars, name)));
+        }
+    }
+
+    if let Some(n) = n {
+        if !(1..=limits.max_n).contains(&n) {
+            return Err(OpenAIError::Validation(format!("Invalid n: {}. It should be between 1 and {} for {}.", n, limits.max_n, name)));
+        }
+    }
+
+    if let Some(size) = size {
+        if !limits.sizes.contains(&size) {
+            return Err(OpenAIError::Validation(format!("Invalid size: {}. It should be one of {} for {}.", size, join(limits.sizes), name)));
+        }
+    }
+
+    if let Some(quality) = quality {
+        if !limits.qualities.contains(&quality) {
+            return Err(OpenAIError::Validation(format!("Invalid quality: {}. It should be one of {} for {}.", quality, join(limits.qualities), name)));
+        }
+    }
+
+    if limits.gpt_image && response_format.is_some() {
+        return Err(OpenAIError::Validation(format!("Invalid response_format: {} always returns b64_json.", name)));
+    }
+
+    Ok(())
+}
+
+impl ImageRequestBuilder {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        let model = self.model.as_ref().and_then(|model| model.as_deref());
+        let background = self.background.flatten();
+        let output_format = self.output_format.flatten();
+
+        validate_image_options(
+            model,
+            self.prompt.as_deref(),
+            self.n.flatten(),
+            self.size.flatten(),
+            self.quality.flatten(),
+            self.response_format.as_ref().and_then(|format| format.as_deref()),
+        )?;
+
+        let Some(limits) = image_model_limits(model) else { return Ok(()) };
+        let name = model.unwrap_or(limits.name);
+
+        if let Some(Some(style)) = self.style {
+            if !limits.styles {
+                return Err(OpenAIError::Validation(format!("Invalid style: {}. It is only supported by dall-e-3, not {}.", style, name)));
+            }
+        }
+
+        if !limits.gpt_image {
+            let unsupported = [
+                background.map(|_| "background"),
+                output_format.map(|_| "output_format"),
+                self.output_compression.flatten().map(|_| "output_compression"),
+            ];
+            if let Some(field) = unsupported.into_iter().flatten().next() {
+                return Err(OpenAIError::Validation(format!("Invalid {}: it is only supported by gpt-image models, not {}.", field, name)));
+            }
+        }
+
+        if let Some(Some(output_compression)) = self.output_compression {
+            if output_compression > 100 {
+                return Err(OpenAIError::Validation(format!("Invalid output_compression: {}. It should be between 0 and 100.", output_compression)));
+            }
+            if !matches!(output_format, Some(ImageOutputFormat::Jpeg | ImageOutputFormat::Webp)) {
+                return Err(OpenAIError::Validation("Invalid output_compression: it requires output_format jpeg or webp.".to_string()));
+            }
+        }
+
+        if background == Some(ImageBackground::Transparent) && output_format == Some(ImageOutputFormat::Jpeg) {
+            return Err(OpenAIError::Validation("Invalid background: transparent requires output_format png or webp.".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+impl ImageEditRequestBuilder {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        if self.image.as_ref().is_none_or(String::is_empty) {
+            return Err(OpenAIError::Validation("Invalid image: it should not be empty.".to_string()));
+        }
+
+        if self.prompt.as_ref().is_none_or(String::is_empty) {
+            return Err(OpenAIError::Validation("Invalid prompt: it should not be empty.".to_string()));
+        }
+
+        validate_image_options(None, self.prompt.as_deref(), self.n.flatten(), self.size.flatten(), None, self.response_format.as_ref().and_then(|format| format.as_deref()))
+    }
+}
+
+impl ImageVariationRequestBuilder {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        if self.image.as_ref().is_none_or(String::is_empty) {
+            return Err(OpenAIError::Validation("Invalid image: it should not be empty.".to_string()));
+        }
+
+        validate_image_options(None, None, self.n.flatten(), self.size.flatten(), None, self.response_format.as_ref().and_then(|format| format.as_deref()))
+    }
+}
+
+impl EmbeddingRequestBuilder {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        let (count, has_empty) = match &self.input {
+            None => return Err(OpenAIError::Validation("Invalid input: it should not be empty.".to_string())),
+            Some(EmbeddingInput::Text(text)) => (1, text.is_empty()),
+            Some(EmbeddingInput::TextArray(texts)) => (texts.len(), texts.iter().any(String::is_empty)),
+            Some(EmbeddingInput::Tokens(tokens)) => (1, tokens.is_empty()),
+            Some(EmbeddingInput::TokenArrays(tokens)) => (tokens.len(), tokens.iter().any(Vec::is_empty)),
+        };
+
+        if count == 0 || has_empty {
+            return Err(OpenAIError::Validation("Invalid input: it should not be empty.".to_string()));
+        }
+
+        if count > 2048 {
+            return Err(OpenAIError::Validation(format!("Invalid input: {} items. It should contain at most 2048 items.", count)));
+        }
+
+        if let Some(Some(dimensions)) = self.dimensions {
+            if dimensions == 0 {
+                return Err(OpenAIError::Validation("Invalid dimensions: 0. It should be greater than 0.".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ModerationRequestBuilder {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        let is_empty = match &self.input {
+            None => true,
+            Some(ModerationInput::Text(text)) => text.is_empty(),
+            Some(ModerationInput::TextArray(texts)) => texts.is_empty(),
+            Some(ModerationInput::Parts(parts)) => parts.is_empty(),
+        };
+
+        if is_empty {
+            return Err(OpenAIError::Validation("Invalid input: it should not be empty.".to_string()));
+        }
+
+        if let (Some(ModerationInput::Parts(parts)), Some(Some(model))) = (&self.input, &self.model) {
+            let has_image = parts.iter().any(|part| matches!(part, ModerationPart::ImageUrl { .. }));
+            if has_image && !model.starts_with("omni-moderation") {
+                return Err(OpenAIError::Validation(format!("Invalid model: {}. Image inputs require an omni-moderation model.", model)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FineTuningJobRequestBuilder {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        if self.model.as_ref().is_none_or(String::is_empty) {
+            return Err(OpenAIError::Validation("Invalid model: it should not be empty.".to_string()));
+        }
+
+        if self.training_file.as_ref().is_none_or(String::is_empty) {
+            return Err(OpenAIError::Validation("Invalid training_file: it should not be empty.".to_string()));
+        }
+
+        if let Some(Some(suffix)) = &self.suffix {
+            if suffix.is_empty() || suffix.len() > 64 {
+                return Err(OpenAIError::Validation(format!("Invalid suffix: {}. It should be between 1 and 64 characters.", suffix)));
+            }
+        }
+
+        if let Some(Some(hyperparameters)) = &self.hyperparameters {
+            if let Some(Hyperparameter::Value(batch_size)) = hyperparameters.batch_size {
+                if !(1..=256).contains(&batch_size) {
+                    return Err(OpenAIError::Validation(format!("Invalid batch_size: {}. It should be between 1 and 256.", batch_size)));
+                }
+            }
+            if let Some(Hyperparameter::Value(multiplier)) = hyperparameters.learning_rate_multiplier {
+                if multiplier <= 0.0 || multiplier > 10.0 {
+                    return Err(OpenAIError::Validation(format!("Invalid learning_rate_multiplier: {}. It should be greater than 0.0 and at most 10.0.", multiplier)));
+                }
+            }
+            if let Some(Hyperparameter::Value(n_epochs)) = hyperparameters.n_epochs {
+                if !(1..=50).contains(&n_epochs) {
+                    return Err(OpenAIError::Validation(format!("Invalid n_epochs: {}. It should be between 1 and 50.", n_epochs)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl BatchRequestBuilder {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        if self.input_file_id.as_ref().is_none_or(String::is_empty) {
+            return Err(OpenAIError::Validation("Invalid input_file_id: it should not be empty.".to_string()));
+        }
+
+        if let Some(completion_window) = &self.completion_window {
+            if completion_window != "24h" {
+                return Err(OpenAIError::Validation(format!("Invalid completion_window: {}. It should be 24h.", completion_window)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl SpeechRequestBuilder {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        match &self.input {
+            Some(input) if input.chars().count() > 4096 => {
+                return Err(OpenAIError::Validation(format!("Invalid input: {} characters. It should be at most 4096 characters.", input.chars().count())));
+            }
+            Some(input) if !input.is_empty() => {}
+            _ => return Err(OpenAIError::Validation("Invalid input: it should not be empty.".to_string())),
+        }
+
+        if let Some(Some(speed)) = self.speed {
+            if !(0.25..=4.0).contains(&speed) {
+                return Err(OpenAIError::Validation(format!("Invalid speed: {}. It should be between 0.25 and 4.0.", speed)));
+            }
+        }
+
+        if let (Some(Some(_)), Some(model)) = (&self.instructions, &self.model) {
+            if model == "tts-1" || model == "tts-1-hd" {
+                return Err(OpenAIError::Validation(format!("Invalid instructions: they are not supported by {}.", model)));
+            }
+        }
+
+        Ok(())
+    }
+}
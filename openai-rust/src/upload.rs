@@ -0,0 +1,84 @@
+use crate::error::OpenAIError;
+use bytes::Bytes;
+use reqwest::{Body, multipart::Part};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::io::AsyncRead;
+use tokio_util::codec::{BytesCodec, FramedRead};
+
+type Reader = Box<dyn AsyncRead + Send + Unpin>;
+
+enum FileSource {
+    Path(PathBuf),
+    Bytes(Bytes),
+    Reader(Mutex<Option<Reader>>),
+}
+
+/// A file sent as a multipart part. Paths and bytes can be resent when a request is retried,
+/// readers are consumed by the first attempt.
+pub struct FileUpload {
+    source: FileSource,
+    file_name: String,
+    mime_type: String,
+}
+
+impl FileUpload {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, OpenAIError> {
+        let path = path.as_ref();
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| OpenAIError::Validation("Invalid file name".to_string()))?
+            .to_str()
+            .ok_or_else(|| OpenAIError::Validation("Non UTF-8 file name".to_string()))?
+            .to_string();
+        let mime_type = mime_guess::from_path(path).first_or_octet_stream().to_string();
+
+        Ok(Self { source: FileSource::Path(path.to_path_buf()), file_name, mime_type })
+    }
+
+    pub fn from_bytes(bytes: impl Into<Bytes>, file_name: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        Self { source: FileSource::Bytes(bytes.into()), file_name: file_name.into(), mime_type: mime_type.into() }
+    }
+
+    pub fn from_reader(reader: impl AsyncRead + Send + Unpin + 'static, file_name: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        Self {
+            source: FileSource::Reader(Mutex::new(Some(Box::new(reader)))),
+            file_name: file_name.into(),
+            mime_type: mime_type.into(),
+        }
+    }
+
+    pub fn file_name(mut self, file_name: impl Into<String>) -> Self {
+        self.file_name = file_name.into();
+        self
+    }
+
+    pub fn mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.mime_type = mime_type.into();
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.file_name
+    }
+
+    pub(crate) fn is_repeatable(&self) -> bool {
+        !matches!(self.source, FileSource::Reader(_))
+    }
+
+    pub(crate) async fn part(&self) -> Result<Part, OpenAIError> {
+        let body = match &self.source {
+            FileSource::Path(path) => Body::wrap_stream(FramedRead::new(tokio::fs::File::open(path).await?, BytesCodec::new())),
+            FileSource::Bytes(bytes) => Body::from(bytes.clone()),
+            FileSource::Reader(reader) => {
+                let reader = reader.lock().ok().and_then(|mut reader| reader.take())
+                    .ok_or_else(|| OpenAIError::Validation(format!("File {} has already been sent", self.file_name)))?;
+                Body::wrap_stream(FramedRead::new(reader, BytesCodec::new()))
+            }
+        };
+
+        Ok(Part::stream(body)
+            .file_name(self.file_name.clone())
+            .mime_str(&self.mime_type)?)
+    }
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "derive")]
+
+use openai_rust::types::{Parameters, ResponseFormat};
+use openai_rust::{FunctionParameters, SchemaProperty};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+fn schema<T: FunctionParameters>() -> Value {
+    serde_json::to_value(T::parameters()).unwrap()
+}
+
+fn required(parameters: &Parameters) -> Vec<&str> {
+    let mut required: Vec<&str> = parameters.required.iter().map(String::as_str).collect();
+    required.sort();
+    required
+}
+
+/// Arguments of a weather lookup
+#[allow(dead_code)]
+#[derive(Deserialize, FunctionParameters)]
+struct Weather {
+    /// The city and state,
+    /// e.g. San Francisco, CA
+    location: String,
+    unit: Option<Unit>,
+    #[serde(default)]
+    days: u32,
+    #[serde(skip)]
+    cache: Vec<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Deserialize, FunctionParameters)]
+#[serde(rename_all = "snake_case")]
+enum Unit {
+    Celsius,
+    Fahrenheit,
+    #[serde(rename = "K")]
+    Kelvin,
+    #[serde(skip)]
+    Rankine,
+}
+
+#[test]
+fn uses_doc_comments_as_descriptions() {
+    let schema = schema::<Weather>();
+    assert_eq!(schema["properties"]["location"]["description"], json!("The city and state, e.g. San Francisco, CA"));
+    assert_eq!(Weather::property().description.as_deref(), Some("Arguments of a weather lookup"));
+}
+
+#[test]
+fn requires_fields_without_option_or_default() {
+    assert_eq!(required(&Weather::parameters()), vec!["location"]);
+    assert!(Weather::parameters().properties.contains_key("days"));
+}
+
+#[test]
+fn skips_fields_and_variants() {
+    let schema = schema::<Weather>();
+    assert_eq!(schema["properties"].get("cache"), None);
+    assert_eq!(schema["properties"]["unit"]["enum"], json!(["celsius", "fahrenheit", "K"]));
+}
+
+#[allow(dead_code)]
+#[derive(Deserialize, FunctionParameters)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct Search {
+    query_text: String,
+    #[serde(rename = "max")]
+    max_results: Option<u32>,
+}
+
+#[test]
+fn renames_fields() {
+    let parameters = Search::parameters();
+    let mut names: Vec<&str> = parameters.properties.keys().map(String::as_str).collect();
+    names.sort();
+    assert_eq!(names, vec!["max", "queryText"]);
+    assert_eq!(required(&parameters), vec!["queryText"]);
+}
+
+#[allow(dead_code)]
+#[derive(Default, Deserialize, FunctionParameters)]
+#[serde(default)]
+struct Defaults {
+    limit: u32,
+    offset: u32,
+}
+
+#[test]
+fn container_default_makes_every_field_optional() {
+    assert!(Defaults::parameters().required.is_empty());
+    assert_eq!(Defaults::parameters().properties.len(), 2);
+}
+
+#[allow(dead_code)]
+#[derive(Deserialize, FunctionParameters)]
+struct Page {
+    cursor: Option<String>,
+    size: u32,
+}
+
+#[allow(dead_code)]
+#[derive(Deserialize, FunctionParameters)]
+struct Listing {
+    filter: String,
+    #[serde(flatten)]
+    page: Page,
+}
+
+#[test]
+fn merges_flattened_fields_into_the_parent() {
+    let parameters = Listing::parameters();
+    let mut names: Vec<&str> = parameters.properties.keys().map(String::as_str).collect();
+    names.sort();
+    assert_eq!(names, vec!["cursor", "filter", "size"]);
+    assert_eq!(required(&parameters), vec!["filter", "size"]);
+
+    let listing: Listing = serde_json::from_value(json!({ "filter": "open", "size": 10, "cursor": null })).unwrap();
+    assert_eq!(listing.page.size, 10);
+}
+
+#[test]
+fn strict_schema_only_makes_option_fields_nullable() {
+    let ResponseFormat::JsonSchema { json_schema } = ResponseFormat::json_schema::<Weather>().unwrap() else {
+        panic!("expected a JSON schema response format");
+    };
+    assert_eq!(json_schema.schema["properties"]["days"], json!({ "type": "integer" }));
+    assert_eq!(json_schema.schema["properties"]["unit"]["anyOf"][1], json!({ "type": "null" }));
+}